@@ -1,22 +1,249 @@
-use anyhow::Result;
+use std::sync::Arc;
 
-use crate::{prelude::*, widgets::ViewID};
+use anyhow::{Context, Result};
+use atrium_api::types::string::Did;
+
+use crate::{
+    atp::{
+        recorded_session_path, AccountStore, EncryptedStore, FileStore, Quota, RateLimit, Recorder,
+        Replayer, Response, RetryClient, RetryPolicy, SharedHttpClient,
+    },
+    config::Config,
+    prelude::*,
+    tui::Waker,
+    widgets::ViewID,
+};
+
+#[cfg(test)]
+pub mod testing;
 
 pub struct App {
     running: bool,
-    pub atp: Atp,
+    config: Config,
+    store: AccountStore,
+    http: SharedHttpClient,
+    rate_limit: RateLimit,
+    /// Agents of all accounts, one per account.
+    accounts: Vec<Atp>,
+    active: usize,
+    /// The agent signing in to another account while the active one stays signed in.
+    signing_in: Option<Atp>,
+    account_changed: bool,
+    /// The last error reported by pages.
+    error: Option<String>,
     view_id: ViewID,
     new_view_id: Option<ViewID>,
+    waker: Waker,
 }
 
 impl App {
-    fn new() -> Result<Self> {
-        Ok(Self {
+    async fn new(config: Config, waker: Waker) -> Result<Self> {
+        let mut store = if config.encrypt_session {
+            AccountStore::Encrypted(EncryptedStore::new()?)
+        } else {
+            AccountStore::File(FileStore::new()?)
+        };
+        let mut http = crate::atp::http_client()?;
+        let mut policy = RetryPolicy::default();
+        if let Some(dir) = &config.record {
+            http = Arc::new(Recorder::new(http, dir)?);
+            store.export(recorded_session_path(dir)).await?;
+        } else if let Some(dir) = &config.replay {
+            http = Arc::new(Replayer::load(dir)?);
+            // The recorded failures are served again without waiting
+            policy.wait = false;
+            // Never touch the saved sessions or the recording while replaying
+            let path = crate::utils::local_data_dir()?.join("replay-session.json");
+            std::fs::copy(recorded_session_path(dir), &path)
+                .context("the recording has no sessions")?;
+            store = AccountStore::File(FileStore::with_path(path));
+        }
+        let http = RetryClient::new(http).with_policy(policy);
+        Ok(Self::with_client(config, store, http, waker).await)
+    }
+
+    /// Creates the app sending all requests through `http`.
+    /// The responses wake the event loop through `waker`.
+    pub async fn with_client(
+        config: Config,
+        store: AccountStore,
+        http: RetryClient,
+        waker: Waker,
+    ) -> Self {
+        let rate_limit = http.rate_limit();
+        let http: SharedHttpClient = Arc::new(http);
+        let sessions = store.load().await.unwrap_or_default();
+        let mut accounts = Vec::new();
+        let mut active = 0;
+        for (did, session) in &sessions.sessions {
+            let Ok(did) = did.parse::<Did>() else {
+                continue;
+            };
+            if sessions.active.as_ref() == Some(&did) {
+                active = accounts.len();
+            }
+            let store = store.account(Some(did));
+            // Encrypted sessions are read once the store is unlocked
+            accounts.push(Atp::new(
+                &config,
+                Arc::clone(&http),
+                store,
+                session.session(),
+                waker.clone(),
+            ));
+        }
+        if accounts.is_empty() {
+            accounts.push(Atp::new(
+                &config,
+                Arc::clone(&http),
+                store.account(None),
+                None,
+                waker.clone(),
+            ));
+        }
+        let view_id = if store.is_locked() {
+            ViewID::Unlock
+        } else {
+            ViewID::default()
+        };
+        Self {
             running: true,
-            atp: Atp::new()?,
-            view_id: ViewID::default(),
+            config,
+            store,
+            http,
+            rate_limit,
+            accounts,
+            active,
+            signing_in: None,
+            account_changed: false,
+            error: None,
+            view_id,
             new_view_id: None,
-        })
+            waker,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.store.is_locked()
+    }
+
+    /// Decrypts the sessions with `passphrase`.
+    pub fn unlock(&self, passphrase: String) -> Response<Result<()>> {
+        let store = self.store.clone();
+        Response::new(&self.waker, async move { store.unlock(passphrase).await })
+    }
+
+    pub fn rate_limit(&self) -> Quota {
+        self.rate_limit.get()
+    }
+
+    /// Returns the waker of the event loop, for the responses not sent through an agent.
+    pub fn waker(&self) -> &Waker {
+        &self.waker
+    }
+
+    /// Returns the agent of the active account.
+    pub fn atp(&self) -> &Atp {
+        &self.accounts[self.active]
+    }
+
+    pub fn accounts(&self) -> &[Atp] {
+        &self.accounts
+    }
+    pub fn active_account(&self) -> usize {
+        self.active
+    }
+
+    /// Makes the account at `index` active and resumes its session.
+    pub fn switch_account(&mut self, index: usize) {
+        if index == self.active || index >= self.accounts.len() {
+            return;
+        }
+        self.active = index;
+        self.account_changed = true;
+        self.set_view_id(ViewID::Login {
+            resume_session: true,
+        });
+    }
+
+    /// Switches to an account that has not signed in yet.
+    pub fn add_account(&mut self) {
+        let index = match self.accounts.iter().position(|a| a.account().did.is_none()) {
+            Some(index) => index,
+            None => {
+                let atp = Atp::new(
+                    &self.config,
+                    Arc::clone(&self.http),
+                    self.store.account(None),
+                    None,
+                    self.waker.clone(),
+                );
+                self.accounts.push(atp);
+                self.accounts.len() - 1
+            }
+        };
+        self.switch_account(index);
+        self.set_view_id(ViewID::Login {
+            resume_session: false,
+        });
+    }
+
+    /// Signs in with a password.
+    /// A new agent signs in if the active account already has, so that it stays in the list.
+    pub fn login(
+        &mut self,
+        ident: String,
+        passwd: String,
+        auth_factor_token: Option<String>,
+    ) -> Response<crate::atp::LoginResult> {
+        if self.atp().account().did.is_none() {
+            self.signing_in = None;
+            return self.atp().login(ident, passwd, auth_factor_token);
+        }
+        let atp = Atp::new(
+            &self.config,
+            Arc::clone(&self.http),
+            self.store.account(None),
+            None,
+            self.waker.clone(),
+        );
+        let response = atp.login(ident, passwd, auth_factor_token);
+        self.signing_in = Some(atp);
+        response
+    }
+
+    /// Makes the account that has just signed in active and drops its other agents.
+    pub fn signed_in(&mut self) {
+        if let Some(atp) = self.signing_in.take() {
+            self.accounts.push(atp);
+            self.active = self.accounts.len() - 1;
+            self.account_changed = true;
+        }
+        let did = self.atp().account().did;
+        let active = self.active;
+        let mut index = 0;
+        let mut removed_before_active = 0;
+        self.accounts.retain(|atp| {
+            let keep = index == active || atp.account().did != did;
+            if !keep && index < active {
+                removed_before_active += 1;
+            }
+            index += 1;
+            keep
+        });
+        self.active -= removed_before_active;
+    }
+
+    /// Shows the error to the user and writes it to the log.
+    pub fn report_error(&mut self, err: anyhow::Error) {
+        tracing::error!("{err:#}");
+        self.error = Some(format!("{err:#}"));
+    }
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    pub fn clear_error(&mut self) {
+        self.error = None;
     }
 
     pub fn view_id(&self) -> &ViewID {
@@ -37,38 +264,61 @@ pub trait EventHandler {
     fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {}
     fn on_mouse(&mut self, ev: crossterm::event::MouseEvent, app: &mut App) {}
     fn on_input(&mut self, input: tui_textarea::Input, app: &mut App) {}
+    /// Called when the user asks to retry after an error is reported.
+    fn on_retry(&mut self, app: &mut App) {}
     fn focus_in_textarea(&self) -> bool {
         false
     }
+    /// Returns `true` while the page shows an animation, such as a spinner.
+    /// The view is rendered periodically only while it is animating.
+    fn is_animating(&self) -> bool {
+        false
+    }
 }
 
-pub async fn run() -> Result<()> {
-    let mut app = App::new()?;
+pub async fn run(config: Config) -> Result<()> {
     let mut tui = Tui::new()?;
+    let mut app = App::new(config, tui.waker()).await?;
 
     let mut view = View::default();
 
     tracing::trace!("start main loop");
     while let Some(event) = tui.event().await {
-        view.on_render(&mut app);
-        if view.focus_in_textarea() {
-            view.on_input(event.into(), &mut app);
-        } else if let TuiEvent::Key(ev) = event {
-            view.on_key(ev, &mut app);
-        } else if let TuiEvent::Mouse(ev) = event {
-            view.on_mouse(ev, &mut app);
-        }
-
+        handle_event(&mut app, &mut view, event);
         if !app.running {
             break;
         }
-        if let Some(s) = app.new_view_id.take() {
-            tracing::info!("set view ID: {s:?}");
-            app.view_id = s;
-        }
         tui.render(&view)?;
     }
 
     tracing::trace!("stop main loop");
     Ok(())
 }
+
+/// Passes `event` to the view and applies the changes the pages have requested.
+fn handle_event(app: &mut App, view: &mut View, event: TuiEvent) {
+    view.on_render(app);
+    if view.focus_in_textarea() {
+        view.on_input(event.into(), app);
+    } else if let TuiEvent::Key(ev) = event {
+        view.on_key(ev, app);
+    } else if let TuiEvent::Mouse(ev) = event {
+        view.on_mouse(ev, app);
+    }
+
+    if !app.running {
+        return;
+    }
+    if std::mem::take(&mut app.account_changed) {
+        tracing::info!("switch to account #{}", app.active);
+        // Pages hold the data of the previous account
+        *view = View::default();
+    }
+    if let Some(s) = app.new_view_id.take() {
+        tracing::info!("set view ID: {s:?}");
+        if app.view_id != s {
+            app.error = None;
+        }
+        app.view_id = s;
+    }
+}
@@ -0,0 +1,118 @@
+//! Drives the whole app against a [`FixtureClient`] in tests.
+
+use std::{sync::Arc, time::Duration};
+
+use atrium_api::agent::{store::SessionStore, Session};
+use crossterm::event::{KeyEvent, KeyModifiers};
+use ratatui::{backend::TestBackend, Terminal};
+use tokio::sync::mpsc;
+
+use super::*;
+use crate::atp::{fixture::FixtureClient, RetryPolicy};
+
+pub struct Harness {
+    pub app: App,
+    pub view: View,
+    pub client: Arc<FixtureClient>,
+    terminal: Terminal<TestBackend>,
+    /// The wakes of the tasks spawned from the test
+    wakes: mpsc::UnboundedReceiver<TuiEvent>,
+    _dir: tempfile::TempDir,
+}
+
+impl Harness {
+    pub async fn new(client: FixtureClient) -> Self {
+        Self::with_session(client, None).await
+    }
+
+    /// Starts with the session of [`FixtureClient::session`] saved.
+    pub async fn signed_in(client: FixtureClient) -> Self {
+        Self::with_session(client, Some(FixtureClient::session())).await
+    }
+
+    async fn with_session(client: FixtureClient, session: Option<Session>) -> Self {
+        let (tx, wakes) = mpsc::unbounded_channel();
+        let dir = tempfile::tempdir().unwrap();
+        let store = AccountStore::File(FileStore::with_path(dir.path().join("session.json")));
+        if let Some(session) = session {
+            let store = store.account(None);
+            store.set_session(session).await;
+            store.set_active().await.unwrap();
+        }
+        let client = Arc::new(client);
+        // Retry without waiting to keep tests fast
+        let http = RetryClient::new(client.clone()).with_policy(RetryPolicy {
+            wait: false,
+            ..Default::default()
+        });
+        let app = App::with_client(Config::default(), store, http, Waker::from(tx)).await;
+        Self {
+            app,
+            view: View::default(),
+            client,
+            terminal: Terminal::new(TestBackend::new(100, 40)).unwrap(),
+            wakes,
+            _dir: dir,
+        }
+    }
+
+    pub fn send(&mut self, event: TuiEvent) {
+        handle_event(&mut self.app, &mut self.view, event);
+        self.terminal
+            .draw(|f| f.render_widget(&self.view, f.size()))
+            .unwrap();
+    }
+
+    pub fn key(&mut self, code: KeyCode) {
+        self.send(TuiEvent::Key(KeyEvent::new(code, KeyModifiers::NONE)));
+    }
+
+    pub fn ctrl(&mut self, c: char) {
+        self.send(TuiEvent::Key(KeyEvent::new(
+            KeyCode::Char(c),
+            KeyModifiers::CONTROL,
+        )));
+    }
+
+    pub fn type_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.key(KeyCode::Char(c));
+        }
+    }
+
+    /// Ticks until `f` returns `true`, letting the spawned requests finish.
+    pub async fn wait_until(&mut self, f: impl Fn(&Self) -> bool) {
+        for _ in 0..500 {
+            self.send(TuiEvent::Tick);
+            if f(self) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("timed out, the screen is:\n{}", self.screen());
+    }
+
+    /// Waits until Home shows the fixture timeline.
+    pub async fn wait_for_timeline(&mut self) {
+        self.wait_until(|h| h.screen().contains("Hello from the fixture timeline!"))
+            .await;
+    }
+
+    /// Waits for a task to wake the view and renders it, as the event loop does without ticks.
+    pub async fn wake(&mut self) {
+        let event = tokio::time::timeout(Duration::from_secs(600), self.wakes.recv())
+            .await
+            .expect("timed out waiting for a wake")
+            .unwrap();
+        self.send(event);
+    }
+
+    pub fn screen(&self) -> String {
+        let buf = self.terminal.backend().buffer();
+        buf.content
+            .chunks(buf.area.width as usize)
+            .map(|cells| cells.iter().map(|c| c.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
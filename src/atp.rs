@@ -1,81 +1,845 @@
+mod client;
+#[cfg(test)]
+pub mod fixture;
+mod identity;
+mod recording;
 mod response;
+mod retry;
+mod richtext;
 mod session;
+#[cfg(test)]
+mod test_server;
 
-use std::sync::Arc;
+use std::{
+    future::Future,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use anyhow::{Context, Result};
-use atrium_api::{agent::AtpAgent, app::bsky};
-use atrium_xrpc_client::reqwest::{ReqwestClient, ReqwestClientBuilder};
+use anyhow::{bail, Context, Result};
+use atrium_api::{
+    agent::{AtpAgent, Session},
+    app::bsky,
+    com::atproto::{
+        repo::{self, strong_ref},
+        server,
+    },
+    records,
+    types::string::{AtIdentifier, Datetime, Did, Handle, Nsid},
+    types::Collection,
+    xrpc,
+};
+#[cfg(test)]
+use rstest::rstest;
 use tracing::instrument;
 
-pub use self::response::Response;
-use self::session::FileStore;
+pub use self::client::{http_client, SharedHttpClient};
+pub use self::recording::{recorded_session_path, Recorder, Replayer};
+pub use self::response::{Response, StreamResponse};
+pub use self::retry::{Quota, RateLimit, RetryClient, RetryPolicy};
+pub use self::session::{AccountStore, EncryptedStore, FileStore};
+use self::{client::Client, identity::Resolver};
+use crate::{config::Config, tui::Waker};
 
+/// An agent of one account.
 pub struct Atp {
     agent: Agent,
+    store: AccountStore,
+    resolver: Resolver,
+    account: Arc<RwLock<Account>>,
+    /// The deadline of every request.
+    timeout: Duration,
+    /// Wakes the event loop when a response arrives.
+    waker: Waker,
 }
 
-type Agent = Arc<AtpAgent<FileStore, ReqwestClient>>;
+#[derive(Clone, Debug, Default)]
+pub struct Account {
+    pub did: Option<Did>,
+    pub handle: Option<Handle>,
+    /// The endpoint the agent is currently talking to.
+    pub endpoint: String,
+}
+
+impl Account {
+    fn update(&mut self, session: &Session, endpoint: String) {
+        self.did = Some(session.did.clone());
+        self.handle = Some(session.handle.clone());
+        self.endpoint = endpoint;
+    }
+}
+
+type Agent = Arc<AtpAgent<AccountStore, Client>>;
 
 impl Atp {
-    pub fn new() -> Result<Self> {
-        const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
-        let xrpc_client = ReqwestClientBuilder::new("https://bsky.social")
-            .client(reqwest::Client::builder().user_agent(USER_AGENT).build()?)
-            .build();
-        let session_store = FileStore::new()?;
-        Ok(Self {
-            agent: Arc::new(AtpAgent::new(xrpc_client, session_store)),
-        })
+    /// Creates an agent of the account `store` is bound to, sending requests through `http`.
+    /// `session` is the last known session of the account, if any.
+    pub fn new(
+        config: &Config,
+        http: SharedHttpClient,
+        store: AccountStore,
+        session: Option<&Session>,
+        waker: Waker,
+    ) -> Self {
+        let xrpc_client = Client::new(Arc::clone(&http), &config.service);
+        let mut account = Account {
+            did: store.did(),
+            endpoint: config.service.clone(),
+            ..Default::default()
+        };
+        if let Some(session) = session {
+            account.update(session, config.service.clone());
+        }
+        Self {
+            agent: Arc::new(AtpAgent::new(xrpc_client, store.clone())),
+            store,
+            resolver: Resolver::new(http, &config.plc_directory),
+            account: Arc::new(RwLock::new(account)),
+            timeout: config.timeout,
+            waker,
+        }
     }
 
     fn agent(&self) -> Agent {
         Arc::clone(&self.agent)
     }
 
-    pub fn get_timeline(
+    pub fn account(&self) -> Account {
+        self.account.read().unwrap().clone()
+    }
+
+    /// Publishes a post, detecting mentions, links and hashtags in the text unless it has facets.
+    pub fn create_post(&self, record: bsky::feed::post::Record) -> Response<CreatePostResult> {
+        Response::with_deadline(
+            &self.waker,
+            create_post(self.agent(), self.account().did, record),
+            self.timeout,
+        )
+        .detach()
+    }
+
+    /// Uploads the image file at `path` to embed it in a post.
+    pub fn upload_image(&self, path: PathBuf) -> Response<UploadImageResult> {
+        Response::with_deadline(&self.waker, upload_image(self.agent(), path), self.timeout)
+            .detach()
+    }
+
+    /// Likes `subject`, or unlikes it by deleting `undo`, the URI of the like.
+    /// Returns the URI of the new like.
+    pub fn like(&self, subject: strong_ref::Main, undo: Option<String>) -> Response<ToggleResult> {
+        let record = records::KnownRecord::AppBskyFeedLike(Box::new(bsky::feed::like::Record {
+            created_at: Datetime::now(),
+            subject,
+        }));
+        Response::with_deadline(
+            &self.waker,
+            toggle_record(
+                self.agent(),
+                self.account().did,
+                bsky::feed::Like::nsid(),
+                record,
+                undo,
+            ),
+            self.timeout,
+        )
+        .detach()
+    }
+
+    /// Reposts `subject`, or undoes it by deleting `undo`, the URI of the repost.
+    /// Returns the URI of the new repost.
+    pub fn repost(
         &self,
-        params: bsky::feed::get_timeline::Parameters,
-    ) -> Response<GetTimelineResult> {
-        Response::new(get_timeline(self.agent(), params))
+        subject: strong_ref::Main,
+        undo: Option<String>,
+    ) -> Response<ToggleResult> {
+        let record =
+            records::KnownRecord::AppBskyFeedRepost(Box::new(bsky::feed::repost::Record {
+                created_at: Datetime::now(),
+                subject,
+            }));
+        Response::with_deadline(
+            &self.waker,
+            toggle_record(
+                self.agent(),
+                self.account().did,
+                bsky::feed::Repost::nsid(),
+                record,
+                undo,
+            ),
+            self.timeout,
+        )
+        .detach()
+    }
+
+    /// Follows `subject`, or unfollows it by deleting `undo`, the URI of the follow.
+    /// Returns the URI of the new follow.
+    pub fn follow(&self, subject: Did, undo: Option<String>) -> Response<ToggleResult> {
+        let record =
+            records::KnownRecord::AppBskyGraphFollow(Box::new(bsky::graph::follow::Record {
+                created_at: Datetime::now(),
+                subject,
+            }));
+        Response::with_deadline(
+            &self.waker,
+            toggle_record(
+                self.agent(),
+                self.account().did,
+                bsky::graph::Follow::nsid(),
+                record,
+                undo,
+            ),
+            self.timeout,
+        )
+        .detach()
+    }
+
+    /// Deletes the post at `uri`, which must have been written by this account.
+    pub fn delete_post(&self, uri: String) -> Response<DeletePostResult> {
+        let agent = self.agent();
+        let did = self.account().did;
+        Response::with_deadline(
+            &self.waker,
+            async move {
+                let did = did.context("not signed in")?;
+                delete_record(&agent, did, &uri).await
+            },
+            self.timeout,
+        )
+        .detach()
+    }
+
+    /// Gets the post at `uri` with its parents and replies.
+    pub fn get_post_thread(&self, uri: String) -> Response<GetPostThreadResult> {
+        Response::with_deadline(
+            &self.waker,
+            get_post_thread(self.agent(), uri),
+            self.timeout,
+        )
+    }
+
+    /// Gets the profile of `actor`, a handle or a DID.
+    pub fn get_profile(&self, actor: String) -> Response<GetProfileResult> {
+        Response::with_deadline(&self.waker, get_profile(self.agent(), actor), self.timeout)
+    }
+
+    /// Gets the pages of `source` one after another from `cursor` as they are taken.
+    pub fn get_feed(
+        &self,
+        source: FeedSource,
+        cursor: Option<String>,
+    ) -> StreamResponse<FeedResult> {
+        let agent = self.agent();
+        paginate(&self.waker, self.timeout, cursor, move |cursor| {
+            get_feed(Arc::clone(&agent), source.clone(), cursor)
+        })
+    }
+
+    /// Gets the pages of the accounts of `source` from `cursor`.
+    pub fn get_actors(
+        &self,
+        source: ActorSource,
+        cursor: Option<String>,
+    ) -> StreamResponse<ActorsResult> {
+        let agent = self.agent();
+        paginate(&self.waker, self.timeout, cursor, move |cursor| {
+            get_actors(Arc::clone(&agent), source.clone(), cursor)
+        })
+    }
+
+    /// Gets the pages of the notifications from `cursor`, the newest first.
+    pub fn get_notifications(&self, cursor: Option<String>) -> StreamResponse<NotificationsResult> {
+        let agent = self.agent();
+        paginate(&self.waker, self.timeout, cursor, move |cursor| {
+            get_notifications(Arc::clone(&agent), cursor)
+        })
+    }
+
+    pub fn get_unread_count(&self) -> Response<UnreadCountResult> {
+        Response::with_deadline(&self.waker, get_unread_count(self.agent()), self.timeout)
     }
 
-    pub fn login(&self, ident: String, passwd: String) -> Response<LoginResult> {
-        Response::new(login(self.agent(), ident, passwd))
+    /// Marks the notifications until `seen_at` as read.
+    pub fn update_seen(&self, seen_at: Datetime) -> Response<UpdateSeenResult> {
+        Response::with_deadline(
+            &self.waker,
+            update_seen(self.agent(), seen_at),
+            self.timeout,
+        )
+        .detach()
+    }
+
+    /// Signs in with a password.
+    /// `auth_factor_token` is the code sent by email if the server requires it.
+    pub fn login(
+        &self,
+        ident: String,
+        passwd: String,
+        auth_factor_token: Option<String>,
+    ) -> Response<LoginResult> {
+        Response::with_deadline(
+            &self.waker,
+            login(
+                self.agent(),
+                self.store.clone(),
+                self.resolver.clone(),
+                Arc::clone(&self.account),
+                server::create_session::Input {
+                    auth_factor_token,
+                    identifier: ident,
+                    password: passwd,
+                },
+            ),
+            self.timeout,
+        )
     }
 
     pub fn resume_session(&self) -> Response<ResumeSessionResult> {
-        Response::new(resume_session(self.agent()))
+        Response::with_deadline(
+            &self.waker,
+            resume_session(self.agent(), self.store.clone(), Arc::clone(&self.account)),
+            self.timeout,
+        )
+    }
+}
+
+/// Where the posts of a feed come from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeedSource {
+    Timeline,
+    /// The posts and reposts of an actor, a handle or a DID.
+    Author(String),
+    /// The posts matching `query`.
+    Search {
+        query: String,
+        sort: SearchSort,
+    },
+}
+
+/// The order of the posts found by a search.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SearchSort {
+    #[default]
+    Top,
+    Latest,
+}
+
+impl SearchSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Latest => "latest",
+        }
     }
 }
 
-pub type GetTimelineResult = Result<bsky::feed::get_timeline::Output>;
+/// The number of items in a page of a paginated list.
+const PAGE_LIMIT: u8 = 15;
+
+/// A page of a paginated list and the cursor to the next page.
+#[derive(Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub cursor: Option<String>,
+}
+
+/// Sends the pages taken by `fetch` from `cursor` one after another,
+/// until the last page or an error.
+fn paginate<T, F, Fut>(
+    waker: &Waker,
+    timeout: Duration,
+    cursor: Option<String>,
+    fetch: F,
+) -> StreamResponse<Result<Page<T>>>
+where
+    T: Send + 'static,
+    F: Fn(Option<String>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Page<T>>> + Send,
+{
+    StreamResponse::new(waker, move |tx| async move {
+        let mut cursor = cursor;
+        loop {
+            let result = response::with_timeout(fetch(cursor.clone()), timeout).await;
+            let next = match &result {
+                Ok(page) => page.cursor.clone(),
+                Err(_) => None,
+            };
+            if tx.send(result).await.is_err() {
+                return;
+            }
+            // Stop at the end of the list or on error
+            if next.is_none() {
+                return;
+            }
+            cursor = next;
+        }
+    })
+}
+
+pub type FeedResult = Result<Page<bsky::feed::defs::FeedViewPost>>;
+
+#[instrument(ret, err, skip(agent))]
+async fn get_feed(agent: Agent, source: FeedSource, cursor: Option<String>) -> FeedResult {
+    let limit = PAGE_LIMIT.try_into().ok();
+    let feed = &agent.api.app.bsky.feed;
+    let (items, cursor) = match source {
+        FeedSource::Timeline => {
+            let output = feed
+                .get_timeline(bsky::feed::get_timeline::Parameters {
+                    algorithm: None,
+                    cursor,
+                    limit,
+                })
+                .await?;
+            (output.feed, output.cursor)
+        }
+        FeedSource::Author(actor) => {
+            let output = feed
+                .get_author_feed(bsky::feed::get_author_feed::Parameters {
+                    actor: parse_actor(&actor)?,
+                    cursor,
+                    filter: None,
+                    limit,
+                })
+                .await?;
+            (output.feed, output.cursor)
+        }
+        FeedSource::Search { query, sort } => {
+            let output = feed
+                .search_posts(bsky::feed::search_posts::Parameters {
+                    author: None,
+                    cursor,
+                    domain: None,
+                    lang: None,
+                    limit,
+                    mentions: None,
+                    q: query,
+                    since: None,
+                    sort: Some(sort.as_str().to_string()),
+                    tag: None,
+                    until: None,
+                    url: None,
+                })
+                .await?;
+            let feed = output
+                .posts
+                .into_iter()
+                .map(|post| bsky::feed::defs::FeedViewPost {
+                    feed_context: None,
+                    post,
+                    reason: None,
+                    reply: None,
+                })
+                .collect();
+            (feed, output.cursor)
+        }
+    };
+    Ok(Page { items, cursor })
+}
+
+/// Which accounts related to an actor to list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActorList {
+    Followers,
+    Follows,
+}
+
+/// Where the accounts of a list come from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActorSource {
+    /// The accounts related to an actor, a handle or a DID.
+    Related { actor: String, list: ActorList },
+    /// The accounts matching a query.
+    Search(String),
+}
+
+pub type ActorsResult = Result<Page<bsky::actor::defs::ProfileView>>;
+
+#[instrument(ret, err, skip(agent))]
+async fn get_actors(agent: Agent, source: ActorSource, cursor: Option<String>) -> ActorsResult {
+    let limit = PAGE_LIMIT.try_into().ok();
+    let api = &agent.api.app.bsky;
+    let (items, cursor) = match source {
+        ActorSource::Related {
+            actor,
+            list: ActorList::Followers,
+        } => {
+            let output = api
+                .graph
+                .get_followers(bsky::graph::get_followers::Parameters {
+                    actor: parse_actor(&actor)?,
+                    cursor,
+                    limit,
+                })
+                .await?;
+            (output.followers, output.cursor)
+        }
+        ActorSource::Related {
+            actor,
+            list: ActorList::Follows,
+        } => {
+            let output = api
+                .graph
+                .get_follows(bsky::graph::get_follows::Parameters {
+                    actor: parse_actor(&actor)?,
+                    cursor,
+                    limit,
+                })
+                .await?;
+            (output.follows, output.cursor)
+        }
+        ActorSource::Search(query) => {
+            let output = api
+                .actor
+                .search_actors(bsky::actor::search_actors::Parameters {
+                    cursor,
+                    limit,
+                    q: Some(query),
+                    term: None,
+                })
+                .await?;
+            (output.actors, output.cursor)
+        }
+    };
+    Ok(Page { items, cursor })
+}
+
+pub type NotificationsResult = Result<Page<bsky::notification::list_notifications::Notification>>;
+
+#[instrument(ret, err, skip(agent))]
+async fn get_notifications(agent: Agent, cursor: Option<String>) -> NotificationsResult {
+    let output = agent
+        .api
+        .app
+        .bsky
+        .notification
+        .list_notifications(bsky::notification::list_notifications::Parameters {
+            cursor,
+            limit: PAGE_LIMIT.try_into().ok(),
+            seen_at: None,
+        })
+        .await?;
+    Ok(Page {
+        items: output.notifications,
+        cursor: output.cursor,
+    })
+}
+
+pub type UnreadCountResult = Result<u64>;
 
 #[instrument(ret, err, skip_all)]
-async fn get_timeline(
+async fn get_unread_count(agent: Agent) -> UnreadCountResult {
+    let output = agent
+        .api
+        .app
+        .bsky
+        .notification
+        .get_unread_count(bsky::notification::get_unread_count::Parameters { seen_at: None })
+        .await?;
+    Ok(output.count.max(0) as u64)
+}
+
+pub type UpdateSeenResult = Result<()>;
+
+#[instrument(ret, err, skip(agent))]
+async fn update_seen(agent: Agent, seen_at: Datetime) -> UpdateSeenResult {
+    agent
+        .api
+        .app
+        .bsky
+        .notification
+        .update_seen(bsky::notification::update_seen::Input { seen_at })
+        .await?;
+    Ok(())
+}
+
+pub type GetProfileResult = Result<bsky::actor::defs::ProfileViewDetailed>;
+
+#[instrument(ret, err, skip(agent))]
+async fn get_profile(agent: Agent, actor: String) -> GetProfileResult {
+    let profile = agent
+        .api
+        .app
+        .bsky
+        .actor
+        .get_profile(bsky::actor::get_profile::Parameters {
+            actor: parse_actor(&actor)?,
+        })
+        .await?;
+    Ok(profile)
+}
+
+/// Parses a handle or a DID, ignoring a leading `@`.
+fn parse_actor(actor: &str) -> Result<AtIdentifier> {
+    let actor = actor.trim().trim_start_matches('@');
+    actor
+        .parse()
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("invalid handle: {actor}"))
+}
+
+pub type GetPostThreadResult = Result<bsky::feed::get_post_thread::Output>;
+
+#[instrument(ret, err, skip(agent))]
+async fn get_post_thread(agent: Agent, uri: String) -> GetPostThreadResult {
+    let thread = agent
+        .api
+        .app
+        .bsky
+        .feed
+        .get_post_thread(bsky::feed::get_post_thread::Parameters {
+            depth: None,
+            parent_height: None,
+            uri,
+        })
+        .await?;
+    Ok(thread)
+}
+
+pub type CreatePostResult = Result<repo::create_record::Output>;
+
+#[instrument(ret, err, skip_all)]
+async fn create_post(
     agent: Agent,
-    params: bsky::feed::get_timeline::Parameters,
-) -> GetTimelineResult {
-    let timeline = agent.api.app.bsky.feed.get_timeline(params).await?;
-    Ok(timeline)
+    did: Option<Did>,
+    record: bsky::feed::post::Record,
+) -> CreatePostResult {
+    let did = did.context("not signed in")?;
+    let mut record = record;
+    if record.facets.is_none() {
+        let facets = richtext::facets(&agent.api, &record.text).await;
+        record.facets = Some(facets).filter(|facets| !facets.is_empty());
+    }
+    create_record(
+        &agent,
+        did,
+        bsky::feed::Post::nsid(),
+        records::KnownRecord::AppBskyFeedPost(Box::new(record)),
+    )
+    .await
+}
+
+/// The largest image the server accepts in a post.
+const MAX_IMAGE_SIZE: usize = 1_000_000;
+
+pub type UploadImageResult = Result<bsky::embed::images::Image>;
+
+#[instrument(err, skip(agent))]
+async fn upload_image(agent: Agent, path: PathBuf) -> UploadImageResult {
+    let data = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("cannot read {}", path.display()))?;
+    if data.len() > MAX_IMAGE_SIZE {
+        bail!("{} is larger than 1 MB", path.display());
+    }
+    // The server detects the type of the image from its contents
+    let output = agent.api.com.atproto.repo.upload_blob(data).await?;
+    Ok(bsky::embed::images::Image {
+        alt: String::new(),
+        aspect_ratio: None,
+        image: output.blob,
+    })
+}
+
+pub type DeletePostResult = Result<()>;
+
+/// The URI of the created record, or `None` if the record has been deleted.
+pub type ToggleResult = Result<Option<String>>;
+
+#[instrument(ret, err, skip(agent, record))]
+async fn toggle_record(
+    agent: Agent,
+    did: Option<Did>,
+    collection: Nsid,
+    record: records::KnownRecord,
+    undo: Option<String>,
+) -> ToggleResult {
+    let did = did.context("not signed in")?;
+    if let Some(uri) = undo {
+        delete_record(&agent, did, &uri).await?;
+        return Ok(None);
+    }
+    let output = create_record(&agent, did, collection, record).await?;
+    Ok(Some(output.uri))
+}
+
+async fn create_record(
+    agent: &Agent,
+    did: Did,
+    collection: Nsid,
+    record: records::KnownRecord,
+) -> Result<repo::create_record::Output> {
+    let output = agent
+        .api
+        .com
+        .atproto
+        .repo
+        .create_record(repo::create_record::Input {
+            collection,
+            record: records::Record::Known(record),
+            repo: did.into(),
+            rkey: None,
+            swap_commit: None,
+            validate: None,
+        })
+        .await?;
+    Ok(output)
+}
+
+/// Deletes the record at `uri` in the repository of `did`.
+async fn delete_record(agent: &Agent, did: Did, uri: &str) -> Result<()> {
+    let (repo, collection, rkey) = parse_at_uri(uri)?;
+    if repo != did.as_str() {
+        bail!("cannot delete a record of another account: {uri}");
+    }
+    let result = agent
+        .api
+        .com
+        .atproto
+        .repo
+        .delete_record(repo::delete_record::Input {
+            collection,
+            repo: did.into(),
+            rkey: rkey.to_string(),
+            swap_commit: None,
+            swap_record: None,
+        })
+        .await;
+    match result {
+        // Newer servers return the commit, which is unknown to this version of the lexicon
+        Ok(()) | Err(xrpc::Error::SerdeJson(_)) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Splits an AT URI of a record `at://<repo>/<collection>/<rkey>`.
+fn parse_at_uri(uri: &str) -> Result<(&str, Nsid, &str)> {
+    let parts = uri
+        .strip_prefix("at://")
+        .map(|path| path.split('/').collect::<Vec<_>>());
+    let Some([repo, collection, rkey]) = parts.as_deref() else {
+        bail!("not an AT URI of a record: {uri}");
+    };
+    let collection = collection.parse().map_err(anyhow::Error::msg)?;
+    Ok((repo, collection, rkey))
 }
 
 pub type LoginResult = Result<()>;
 
+#[derive(Debug)]
+pub enum LoginError {
+    /// The server sent a sign-in code by email and requires it.
+    AuthFactorTokenRequired,
+}
+
+impl std::fmt::Display for LoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AuthFactorTokenRequired => f.write_str("a sign-in code is required"),
+        }
+    }
+}
+
+impl std::error::Error for LoginError {}
+
 #[instrument(ret, err, skip_all)]
-async fn login(agent: Agent, ident: String, passwd: String) -> LoginResult {
-    agent.login(ident, passwd).await?;
+async fn login(
+    agent: Agent,
+    store: AccountStore,
+    resolver: Resolver,
+    account: Arc<RwLock<Account>>,
+    input: server::create_session::Input,
+) -> LoginResult {
+    match resolver.resolve_pds(&agent.api, &input.identifier).await {
+        Ok(Some(pds)) => {
+            agent.configure_endpoint(pds.clone());
+            account.write().unwrap().endpoint = pds;
+        }
+        Ok(None) => {}
+        Err(err) => {
+            tracing::warn!("failed to resolve the PDS, use the configured service: {err}");
+        }
+    }
+    // `AtpAgent::login` cannot send the auth factor token
+    let session = match agent.api.com.atproto.server.create_session(input).await {
+        Ok(session) => session,
+        Err(xrpc::Error::XrpcResponse(xrpc::error::XrpcError {
+            error:
+                Some(xrpc::error::XrpcErrorKind::Custom(
+                    server::create_session::Error::AuthFactorTokenRequired(_),
+                )),
+            ..
+        })) => return Err(LoginError::AuthFactorTokenRequired.into()),
+        Err(err) => return Err(err.into()),
+    };
+    if let Some(pds) = session.did_doc.as_ref().and_then(identity::pds_endpoint) {
+        agent.configure_endpoint(pds);
+    }
+    agent.resume_session(session.clone()).await?;
+    store.set_active().await?;
+    let endpoint = agent.get_endpoint().await;
+    account.write().unwrap().update(&session, endpoint);
     Ok(())
 }
 
 pub type ResumeSessionResult = Result<()>;
 
 #[instrument(ret, err, skip_all)]
-async fn resume_session(agent: Agent) -> ResumeSessionResult {
+async fn resume_session(
+    agent: Agent,
+    store: AccountStore,
+    account: Arc<RwLock<Account>>,
+) -> ResumeSessionResult {
     let session = agent
         .get_session()
         .await
         .context("cannot find an existing session")?;
+    if let Some(pds) = session.did_doc.as_ref().and_then(identity::pds_endpoint) {
+        agent.configure_endpoint(pds);
+    }
     agent.resume_session(session).await?;
+    store.set_active().await?;
+    let session = agent
+        .get_session()
+        .await
+        .context("the session has been cleared")?;
+    let endpoint = agent.get_endpoint().await;
+    account.write().unwrap().update(&session, endpoint);
     Ok(())
 }
+
+#[cfg(test)]
+#[rstest]
+#[case(
+    Some(("did:plc:alice", "app.bsky.feed.like", "3kwapw2bqjs2a")),
+    "at://did:plc:alice/app.bsky.feed.like/3kwapw2bqjs2a"
+)]
+#[case(None, "at://did:plc:alice/app.bsky.feed.like")]
+#[case(None, "at://did:plc:alice/app.bsky.feed.like/3kwapw2bqjs2a/x")]
+#[case(None, "https://did:plc:alice/app.bsky.feed.like/3kwapw2bqjs2a")]
+#[case(None, "at://did:plc:alice/not an nsid/3kwapw2bqjs2a")]
+fn test_parse_at_uri(#[case] expected: Option<(&str, &str, &str)>, #[case] uri: &str) {
+    assert_eq!(
+        expected.map(|(repo, collection, rkey)| (repo, collection.to_string(), rkey)),
+        parse_at_uri(uri)
+            .ok()
+            .map(|(repo, collection, rkey)| (repo, collection.to_string(), rkey)),
+    );
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(Some("alice.test"), "alice.test")]
+#[case(Some("alice.test"), " @alice.test ")]
+#[case(Some("did:plc:alice"), "did:plc:alice")]
+#[case(None, "")]
+#[case(None, "not a handle")]
+fn test_parse_actor(#[case] expected: Option<&str>, #[case] actor: &str) {
+    assert_eq!(
+        expected.map(String::from),
+        parse_actor(actor).ok().map(|actor| match actor {
+            AtIdentifier::Did(did) => did.as_str().to_string(),
+            AtIdentifier::Handle(handle) => handle.as_str().to_string(),
+        }),
+    );
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use atrium_api::xrpc::{
+    http::{Request, Response},
+    HttpClient, XrpcClient,
+};
+use atrium_xrpc_client::reqwest::ReqwestClientBuilder;
+
+/// The transport of all requests sent by an agent.
+pub type SharedHttpClient = Arc<dyn HttpClient + Send + Sync>;
+
+/// Creates the transport talking to the real network.
+pub fn http_client() -> anyhow::Result<SharedHttpClient> {
+    const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+    let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    // The base URI is unused because requests have absolute URIs
+    Ok(Arc::new(
+        ReqwestClientBuilder::new("").client(client).build(),
+    ))
+}
+
+/// An [`XrpcClient`] sending requests through any [`HttpClient`].
+#[derive(Clone)]
+pub struct Client {
+    http: SharedHttpClient,
+    base_uri: String,
+}
+
+impl Client {
+    pub fn new(http: SharedHttpClient, base_uri: impl Into<String>) -> Self {
+        Self {
+            http,
+            base_uri: base_uri.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl HttpClient for Client {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.http.send_http(request).await
+    }
+}
+
+impl XrpcClient for Client {
+    fn base_uri(&self) -> String {
+        self.base_uri.clone()
+    }
+}
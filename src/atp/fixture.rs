@@ -0,0 +1,222 @@
+//! A transport serving canned responses so that the app runs without the network.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use atrium_api::{
+    agent::Session,
+    xrpc::{
+        http::{header, Request, Response, StatusCode},
+        HttpClient,
+    },
+};
+
+/// Serves responses by the path of the request, ignoring the host and the query.
+pub struct FixtureClient {
+    routes: HashMap<String, (StatusCode, Vec<u8>)>,
+    /// The method and the URI of every request sent so far.
+    requests: Mutex<Vec<String>>,
+    /// The bodies of `getSession` by the authorization header, for the accounts other than Alice.
+    sessions: HashMap<String, Vec<u8>>,
+}
+
+impl Default for FixtureClient {
+    /// Serves an account `alice.test` hosted on `https://pds.example.com`.
+    fn default() -> Self {
+        Self {
+            routes: HashMap::new(),
+            requests: Mutex::new(Vec::new()),
+            sessions: HashMap::new(),
+        }
+        .xrpc(
+            "com.atproto.identity.resolveHandle",
+            include_str!("fixtures/resolve_handle.json"),
+        )
+        .xrpc(
+            "com.atproto.server.createSession",
+            include_str!("fixtures/create_session.json"),
+        )
+        .xrpc(
+            "com.atproto.server.getSession",
+            include_str!("fixtures/get_session.json"),
+        )
+        .xrpc(
+            "com.atproto.server.refreshSession",
+            include_str!("fixtures/create_session.json"),
+        )
+        .xrpc(
+            "app.bsky.feed.getTimeline",
+            include_str!("fixtures/get_timeline.json"),
+        )
+        .xrpc(
+            "app.bsky.feed.getAuthorFeed",
+            include_str!("fixtures/get_author_feed.json"),
+        )
+        .xrpc(
+            "app.bsky.actor.getProfile",
+            include_str!("fixtures/get_profile.json"),
+        )
+        .xrpc(
+            "app.bsky.graph.getFollowers",
+            include_str!("fixtures/get_followers.json"),
+        )
+        .xrpc(
+            "app.bsky.graph.getFollows",
+            include_str!("fixtures/get_follows.json"),
+        )
+        .xrpc(
+            "app.bsky.feed.searchPosts",
+            include_str!("fixtures/search_posts.json"),
+        )
+        .xrpc(
+            "app.bsky.actor.searchActors",
+            include_str!("fixtures/search_actors.json"),
+        )
+        .xrpc(
+            "app.bsky.notification.listNotifications",
+            include_str!("fixtures/list_notifications.json"),
+        )
+        .xrpc("app.bsky.notification.getUnreadCount", r#"{"count":3}"#)
+        .xrpc("app.bsky.notification.updateSeen", "")
+        .xrpc(
+            "app.bsky.feed.getPostThread",
+            include_str!("fixtures/get_post_thread.json"),
+        )
+        .xrpc(
+            "com.atproto.repo.createRecord",
+            include_str!("fixtures/create_record.json"),
+        )
+        .xrpc("com.atproto.repo.deleteRecord", "{}")
+        .xrpc(
+            "com.atproto.repo.uploadBlob",
+            include_str!("fixtures/upload_blob.json"),
+        )
+        .route(
+            "/did:plc:alice",
+            StatusCode::OK,
+            include_str!("fixtures/did_doc.json"),
+        )
+    }
+}
+
+impl FixtureClient {
+    /// Serves `body` at `path`, replacing the previous response.
+    pub fn route(mut self, path: &str, status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        self.routes.insert(path.to_string(), (status, body.into()));
+        self
+    }
+
+    pub fn xrpc(self, nsid: &str, body: impl Into<Vec<u8>>) -> Self {
+        self.route(&format!("/xrpc/{nsid}"), StatusCode::OK, body)
+    }
+
+    /// Changes the JSON body served for `nsid` with `patch`.
+    pub fn patch_xrpc(mut self, nsid: &str, patch: impl FnOnce(&mut serde_json::Value)) -> Self {
+        let (_, body) = self
+            .routes
+            .get_mut(&format!("/xrpc/{nsid}"))
+            .unwrap_or_else(|| panic!("no fixture for {nsid}"));
+        let mut value = serde_json::from_slice(body).unwrap();
+        patch(&mut value);
+        *body = value.to_string().into_bytes();
+        self
+    }
+
+    /// Makes `nsid` fail with an XRPC error.
+    pub fn xrpc_error(self, nsid: &str, status: StatusCode, error: &str) -> Self {
+        let body = serde_json::json!({ "error": error, "message": "fixture error" });
+        self.route(&format!("/xrpc/{nsid}"), status, body.to_string())
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// How many times the XRPC method `nsid` has been requested.
+    /// The parameters are compared too if `nsid` has a query, e.g.
+    /// `app.bsky.actor.getProfile?actor=bob.test`.
+    pub fn requested_times(&self, nsid: &str) -> usize {
+        self.requests()
+            .iter()
+            .filter_map(|r| r.split_once("/xrpc/").map(|(_, path)| path))
+            .filter(|path| {
+                if nsid.contains('?') {
+                    *path == nsid
+                } else {
+                    path.split('?').next() == Some(nsid)
+                }
+            })
+            .count()
+    }
+
+    /// Whether the XRPC method `nsid` has been requested; see [`Self::requested_times`].
+    pub fn requested(&self, nsid: &str) -> bool {
+        self.requested_times(nsid) > 0
+    }
+
+    /// The fixture timeline, with the post of Bob in `feed[0]`.
+    pub fn timeline() -> serde_json::Value {
+        serde_json::from_str(include_str!("fixtures/get_timeline.json")).unwrap()
+    }
+
+    /// Serves the fixture timeline changed by `patch`.
+    pub fn with_timeline(self, patch: impl FnOnce(&mut serde_json::Value)) -> Self {
+        self.patch_xrpc("app.bsky.feed.getTimeline", patch)
+    }
+
+    /// The session returned by `createSession`.
+    pub fn session() -> Session {
+        serde_json::from_str(include_str!("fixtures/create_session.json")).unwrap()
+    }
+
+    /// Signs in to `did:plc:{name}` as `{name}.test` instead of Alice.
+    /// The sessions of Alice can still be resumed.
+    pub fn sign_in_as(mut self, name: &str) -> Self {
+        let mut session = serde_json::to_value(Self::session()).unwrap();
+        session["accessJwt"] = format!("{name}-access-jwt").into();
+        session["refreshJwt"] = format!("{name}-refresh-jwt").into();
+        session["did"] = format!("did:plc:{name}").into();
+        session["handle"] = format!("{name}.test").into();
+        session["didDoc"]["id"] = session["did"].clone();
+        session["didDoc"]["alsoKnownAs"] = serde_json::json!([format!("at://{name}.test")]);
+        let account = serde_json::json!({ "did": session["did"], "handle": session["handle"] });
+        self.sessions.insert(
+            format!("Bearer {name}-access-jwt"),
+            account.to_string().into_bytes(),
+        );
+        self.xrpc("com.atproto.server.createSession", session.to_string())
+    }
+}
+
+#[async_trait]
+impl HttpClient for FixtureClient {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push(format!("{} {}", request.method(), request.uri()));
+        let session = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .filter(|_| request.uri().path() == "/xrpc/com.atproto.server.getSession")
+            .and_then(|authorization| self.sessions.get(authorization));
+        let (status, body) = match (session, self.routes.get(request.uri().path())) {
+            (Some(body), _) => (StatusCode::OK, body.clone()),
+            (None, Some((status, body))) => (*status, body.clone()),
+            (None, None) => (
+                StatusCode::NOT_FOUND,
+                br#"{"error":"NotFound","message":"no fixture"}"#.to_vec(),
+            ),
+        };
+        let mut builder = Response::builder().status(status);
+        // Servers send no content type with an empty body
+        if !body.is_empty() {
+            builder = builder.header(header::CONTENT_TYPE, "application/json");
+        }
+        Ok(builder.body(body)?)
+    }
+}
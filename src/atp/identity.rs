@@ -0,0 +1,204 @@
+use anyhow::{bail, Context, Result};
+use atrium_api::{
+    client::Service,
+    com::atproto::identity::resolve_handle,
+    did_doc::DidDocument,
+    types::string::{Did, Handle},
+    xrpc::{http::Request, XrpcClient},
+};
+use tracing::instrument;
+
+use super::client::SharedHttpClient;
+
+/// Resolves handles and DIDs to the PDS that hosts the account.
+#[derive(Clone)]
+pub struct Resolver {
+    http: SharedHttpClient,
+    plc_directory: String,
+}
+
+impl Resolver {
+    pub fn new(http: SharedHttpClient, plc_directory: impl Into<String>) -> Self {
+        Self {
+            http,
+            plc_directory: plc_directory.into(),
+        }
+    }
+
+    /// Returns the PDS endpoint of `ident`,
+    /// or `None` if `ident` cannot be resolved (e.g. an email address).
+    #[instrument(ret, err, skip(self, api))]
+    pub async fn resolve_pds<T>(&self, api: &Service<T>, ident: &str) -> Result<Option<String>>
+    where
+        T: XrpcClient + Send + Sync,
+    {
+        let Some(did) = self.resolve_did(api, ident).await? else {
+            return Ok(None);
+        };
+        let did_doc = self.resolve_did_doc(&did).await?;
+        let pds = pds_endpoint(&did_doc).context("the DID document has no PDS endpoint")?;
+        Ok(Some(pds))
+    }
+
+    async fn resolve_did<T>(&self, api: &Service<T>, ident: &str) -> Result<Option<Did>>
+    where
+        T: XrpcClient + Send + Sync,
+    {
+        let ident = ident.trim().trim_start_matches('@');
+        if ident.starts_with("did:") {
+            return Ok(Some(ident.parse().map_err(anyhow::Error::msg)?));
+        }
+        let Ok(handle) = ident.parse::<Handle>() else {
+            return Ok(None);
+        };
+        let output = api
+            .com
+            .atproto
+            .identity
+            .resolve_handle(resolve_handle::Parameters { handle })
+            .await?;
+        Ok(Some(output.did))
+    }
+
+    async fn resolve_did_doc(&self, did: &Did) -> Result<DidDocument> {
+        let url = match did.method() {
+            "did:plc" => format!("{}/{}", self.plc_directory, did.as_str()),
+            "did:web" => did_web_url(did)?,
+            method => bail!("unsupported DID method: {method}"),
+        };
+        let request = Request::builder().uri(url).body(Vec::new())?;
+        let response = self
+            .http
+            .send_http(request)
+            .await
+            .map_err(|err| anyhow::anyhow!(err))?;
+        if !response.status().is_success() {
+            bail!("failed to get the DID document: {}", response.status());
+        }
+        Ok(serde_json::from_slice(response.body())?)
+    }
+}
+
+fn did_web_url(did: &Did) -> Result<String> {
+    let host = did
+        .as_str()
+        .strip_prefix("did:web:")
+        .context("not a did:web identifier")?;
+    if host.contains(':') {
+        bail!("did:web with a path is not supported");
+    }
+    Ok(format!(
+        "https://{}/.well-known/did.json",
+        host.replace("%3A", ":")
+    ))
+}
+
+pub fn pds_endpoint(did_doc: &DidDocument) -> Option<String> {
+    did_doc
+        .service
+        .as_ref()?
+        .iter()
+        .find(|s| s.id.ends_with("#atproto_pds") && s.r#type == "AtprotoPersonalDataServer")
+        .map(|s| s.service_endpoint.trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::{client::AtpServiceClient, did_doc, xrpc::http};
+    use atrium_xrpc_client::reqwest::ReqwestClient;
+
+    use super::*;
+    use crate::atp::{client, test_server::TestServer};
+
+    fn did_doc(id: &str, endpoint: &str) -> DidDocument {
+        DidDocument {
+            id: id.to_string(),
+            also_known_as: None,
+            verification_method: None,
+            service: Some(vec![did_doc::Service {
+                id: String::from("#atproto_pds"),
+                r#type: String::from("AtprotoPersonalDataServer"),
+                service_endpoint: endpoint.to_string(),
+            }]),
+        }
+    }
+
+    async fn server() -> TestServer {
+        TestServer::start(|req| {
+            let body = match req.uri().path_and_query().unwrap().as_str() {
+                "/xrpc/com.atproto.identity.resolveHandle?handle=alice.example.com" => {
+                    serde_json::json!({ "did": "did:plc:alice" })
+                }
+                "/did:plc:alice" => {
+                    serde_json::to_value(did_doc("did:plc:alice", "https://pds.example.com/"))
+                        .unwrap()
+                }
+                _ => {
+                    return http::Response::builder()
+                        .status(http::StatusCode::NOT_FOUND)
+                        .body(Vec::new())
+                        .unwrap()
+                }
+            };
+            http::Response::builder()
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(serde_json::to_vec(&body).unwrap())
+                .unwrap()
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn resolve_handle() {
+        let server = server().await;
+        let api = AtpServiceClient::new(ReqwestClient::new(server.url())).service;
+        let resolver = Resolver::new(client::http_client().unwrap(), server.url());
+        for ident in ["alice.example.com", "@alice.example.com", "did:plc:alice"] {
+            assert_eq!(
+                Some(String::from("https://pds.example.com")),
+                resolver.resolve_pds(&api, ident).await.unwrap(),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_email() {
+        let server = server().await;
+        let api = AtpServiceClient::new(ReqwestClient::new(server.url())).service;
+        let resolver = Resolver::new(client::http_client().unwrap(), server.url());
+        assert_eq!(
+            None,
+            resolver
+                .resolve_pds(&api, "alice@example.com")
+                .await
+                .unwrap(),
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_unknown_handle() {
+        let server = server().await;
+        let api = AtpServiceClient::new(ReqwestClient::new(server.url())).service;
+        let resolver = Resolver::new(client::http_client().unwrap(), server.url());
+        assert!(resolver.resolve_pds(&api, "bob.example.com").await.is_err());
+    }
+
+    #[test]
+    fn web_did() {
+        assert_eq!(
+            "https://example.com/.well-known/did.json",
+            did_web_url(&"did:web:example.com".parse().unwrap()).unwrap(),
+        );
+        assert_eq!(
+            "https://localhost:8080/.well-known/did.json",
+            did_web_url(&"did:web:localhost%3A8080".parse().unwrap()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn no_pds_endpoint() {
+        let mut doc = did_doc("did:plc:alice", "https://pds.example.com");
+        doc.service.as_mut().unwrap()[0].id = String::from("#atproto_labeler");
+        assert_eq!(None, pds_endpoint(&doc));
+    }
+}
@@ -1,11 +1,22 @@
-use std::cell::RefCell;
+mod stream;
 
-use tokio::sync::oneshot;
+use std::{cell::RefCell, future::Future, time::Duration};
+
+use tokio::{sync::oneshot, task::AbortHandle};
+
+use crate::tui::Waker;
+
+pub use self::stream::StreamResponse;
 
 /// A simple wrapper of [`tokio::sync::oneshot::Receiver`].
+///
+/// The task is aborted when the response is dropped, unless it has been [detached].
+///
+/// [detached]: Response::detach
 #[derive(Debug)]
 pub struct Response<T> {
     inner: Option<RefCell<Inner<T>>>,
+    abort_handle: Option<AbortHandle>,
 }
 
 #[derive(Debug)]
@@ -28,39 +39,104 @@ impl<T> From<oneshot::Receiver<T>> for Response<T> {
         };
         Self {
             inner: Some(RefCell::new(inner)),
+            abort_handle: None,
+        }
+    }
+}
+
+impl<T> Drop for Response<T> {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.abort_handle {
+            handle.abort();
         }
     }
 }
 
+/// The error of a task that has not finished within its deadline.
+#[derive(Debug, PartialEq)]
+pub struct Timeout(pub Duration);
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "timed out after {}s", self.0.as_secs_f32())
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Fails with [`Timeout`] unless `future` finishes within `deadline`.
+pub async fn with_timeout<T>(
+    future: impl Future<Output = anyhow::Result<T>>,
+    deadline: Duration,
+) -> anyhow::Result<T> {
+    match tokio::time::timeout(deadline, future).await {
+        Ok(result) => result,
+        Err(_) => Err(Timeout(deadline).into()),
+    }
+}
+
 impl<T: Send + 'static> Response<T> {
-    /// Spawns an async task.
-    pub fn new(future: impl std::future::Future<Output = T> + Send + 'static) -> Self {
+    /// Spawns an async task, which wakes the event loop through `waker` when it finishes.
+    pub fn new(waker: &Waker, future: impl Future<Output = T> + Send + 'static) -> Self {
         let (tx, rx) = oneshot::channel();
-        tokio::spawn(async {
+        let waker = waker.clone();
+        let handle = tokio::spawn(async move {
             tx.send(future.await).ok();
+            waker.wake();
         });
-        Self::from(rx)
+        let mut response = Self::from(rx);
+        response.abort_handle = Some(handle.abort_handle());
+        response
+    }
+}
+
+impl<T: Send + 'static> Response<anyhow::Result<T>> {
+    /// Spawns an async task that fails with [`Timeout`] unless it finishes within `deadline`.
+    pub fn with_deadline(
+        waker: &Waker,
+        future: impl Future<Output = anyhow::Result<T>> + Send + 'static,
+        deadline: Duration,
+    ) -> Self {
+        Self::new(waker, with_timeout(future, deadline))
     }
 }
 
 impl<T> Response<T> {
     /// Create an empty response.
     pub fn empty() -> Self {
-        Self { inner: None }
+        Self {
+            inner: None,
+            abort_handle: None,
+        }
+    }
+
+    /// Aborts the task. After calling this method, the response will be empty.
+    pub fn cancel(&mut self) {
+        if let Some(handle) = self.abort_handle.take() {
+            handle.abort();
+        }
+        self.inner = None;
+    }
+
+    /// Keeps the task running when the response is dropped, so that a write is not lost when
+    /// the page waiting for it starts over.
+    pub fn detach(mut self) -> Self {
+        self.abort_handle = None;
+        self
     }
 
     /// Returns `true` if the response can never return data.
     pub fn is_empty(&self) -> bool {
         self.inner
             .as_ref()
-            .map_or(true, |r| r.borrow_mut().is_empty())
+            .is_none_or(|r| r.borrow_mut().is_empty())
     }
 
     /// Returns `true` until the task is running.
     pub fn is_loading(&self) -> bool {
         self.inner
             .as_ref()
-            .map_or(false, |r| r.borrow_mut().is_loading())
+            .is_some_and(|r| r.borrow_mut().is_loading())
     }
 
     /// If the data has been received, return it only once.
@@ -108,13 +184,18 @@ impl<T> Inner<T> {
 
 #[cfg(test)]
 mod tests {
-    use tokio::time::{sleep, Duration};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use tokio::time::sleep;
 
     use super::*;
 
     #[tokio::test]
     async fn take_data() {
-        let res = Response::new(async {
+        let res = Response::new(&Waker::default(), async {
             sleep(Duration::from_millis(10)).await;
             1
         });
@@ -129,7 +210,7 @@ mod tests {
 
     #[tokio::test]
     async fn is_loading() {
-        let res = Response::new(async {
+        let res = Response::new(&Waker::default(), async {
             sleep(Duration::from_millis(10)).await;
         });
 
@@ -143,7 +224,7 @@ mod tests {
     async fn is_empty() {
         assert!(Response::<()>::empty().is_empty());
 
-        let res = Response::new(async {
+        let res = Response::new(&Waker::default(), async {
             sleep(Duration::from_millis(10)).await;
             1
         });
@@ -156,4 +237,81 @@ mod tests {
         res.take_data();
         assert!(res.is_empty());
     }
+
+    fn finished_flag() -> (Arc<AtomicBool>, impl std::future::Future<Output = ()>) {
+        let finished = Arc::new(AtomicBool::new(false));
+        let future = {
+            let finished = Arc::clone(&finished);
+            async move {
+                sleep(Duration::from_millis(10)).await;
+                finished.store(true, Ordering::SeqCst);
+            }
+        };
+        (finished, future)
+    }
+
+    #[tokio::test]
+    async fn cancel_on_drop() {
+        let (finished, future) = finished_flag();
+        drop(Response::new(&Waker::default(), future));
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn detach() {
+        let (finished, future) = finished_flag();
+        drop(Response::new(&Waker::default(), future).detach());
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn cancel() {
+        let (finished, future) = finished_flag();
+        let mut res = Response::new(&Waker::default(), future);
+        res.cancel();
+        assert!(res.is_empty());
+        assert!(!res.is_loading());
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(!finished.load(Ordering::SeqCst));
+        assert_eq!(None, res.take_data());
+    }
+
+    #[tokio::test]
+    async fn deadline() {
+        let res = Response::with_deadline(
+            &Waker::default(),
+            async {
+                sleep(Duration::from_secs(10)).await;
+                Ok(1)
+            },
+            Duration::from_millis(10),
+        );
+
+        sleep(Duration::from_millis(50)).await;
+        let err = res.take_data().unwrap().unwrap_err();
+        assert_eq!(
+            Some(&Timeout(Duration::from_millis(10))),
+            err.downcast_ref()
+        );
+    }
+
+    #[tokio::test]
+    async fn within_deadline() {
+        let res = Response::with_deadline(
+            &Waker::default(),
+            async {
+                sleep(Duration::from_millis(10)).await;
+                Ok(1)
+            },
+            Duration::from_secs(10),
+        );
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(1, res.take_data().unwrap().unwrap());
+    }
 }
@@ -0,0 +1,224 @@
+use std::{cell::RefCell, future::Future};
+
+use tokio::{
+    sync::mpsc::{
+        self,
+        error::{SendError, TryRecvError},
+    },
+    task::AbortHandle,
+};
+
+use crate::tui::Waker;
+
+/// Like [`Response`](super::Response), but the task can send several values.
+///
+/// The task can send the next value only after the previous one is taken,
+/// so it does no more work than the receiver asks for.
+/// The task is aborted when the response is dropped.
+#[derive(Debug)]
+pub struct StreamResponse<T> {
+    inner: Option<RefCell<Inner<T>>>,
+    abort_handle: Option<AbortHandle>,
+}
+
+#[derive(Debug)]
+struct Inner<T> {
+    receiver: mpsc::Receiver<T>,
+    received: Option<T>,
+}
+
+/// Sends values to a [`StreamResponse`], waking the event loop for each of them.
+#[derive(Debug)]
+pub struct Sender<T> {
+    tx: mpsc::Sender<T>,
+    waker: Waker,
+}
+
+impl<T> Sender<T> {
+    /// Waits until the previous value is taken, and sends `value`.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.tx.send(value).await?;
+        self.waker.wake();
+        Ok(())
+    }
+}
+
+impl<T> Default for StreamResponse<T> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T> Drop for StreamResponse<T> {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.abort_handle {
+            handle.abort();
+        }
+    }
+}
+
+impl<T: Send + 'static> StreamResponse<T> {
+    /// Spawns an async task that sends values through the given sender,
+    /// which wakes the event loop through `waker` for each of them.
+    pub fn new<F>(waker: &Waker, f: impl FnOnce(Sender<T>) -> F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(1);
+        let handle = tokio::spawn(f(Sender {
+            tx,
+            waker: waker.clone(),
+        }));
+        Self {
+            inner: Some(RefCell::new(Inner {
+                receiver: rx,
+                received: None,
+            })),
+            abort_handle: Some(handle.abort_handle()),
+        }
+    }
+}
+
+impl<T> StreamResponse<T> {
+    /// Create an empty response.
+    pub fn empty() -> Self {
+        Self {
+            inner: None,
+            abort_handle: None,
+        }
+    }
+
+    /// Returns `true` if the response can never return data anymore.
+    pub fn is_empty(&self) -> bool {
+        self.inner
+            .as_ref()
+            .is_none_or(|r| r.borrow_mut().is_empty())
+    }
+
+    /// Returns `true` while the task is running to send the next value.
+    pub fn is_loading(&self) -> bool {
+        self.inner
+            .as_ref()
+            .is_some_and(|r| r.borrow_mut().is_loading())
+    }
+
+    /// Returns the next value if it has been received.
+    pub fn take_data(&self) -> Option<T> {
+        self.inner.as_ref()?.borrow_mut().take_data()
+    }
+}
+
+impl<T> Inner<T> {
+    fn poll(&mut self) -> Result<(), TryRecvError> {
+        if self.received.is_some() {
+            return Ok(());
+        }
+        self.received = Some(self.receiver.try_recv()?);
+        Ok(())
+    }
+
+    fn is_loading(&mut self) -> bool {
+        self.poll() == Err(TryRecvError::Empty)
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.poll() == Err(TryRecvError::Disconnected)
+    }
+
+    fn take_data(&mut self) -> Option<T> {
+        self.poll().ok()?;
+        self.received.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::time::{sleep, Duration};
+
+    use super::*;
+
+    fn count_up(n: usize, sent: Arc<AtomicUsize>) -> StreamResponse<usize> {
+        StreamResponse::new(&Waker::default(), |tx| async move {
+            for i in 0..n {
+                sleep(Duration::from_millis(10)).await;
+                if tx.send(i).await.is_err() {
+                    return;
+                }
+                sent.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn take_data() {
+        let res = count_up(3, Arc::default());
+        assert_eq!(None, res.take_data());
+
+        for i in 0..3 {
+            sleep(Duration::from_millis(50)).await;
+            assert_eq!(Some(i), res.take_data());
+            assert_eq!(None, res.take_data());
+        }
+    }
+
+    #[tokio::test]
+    async fn is_loading() {
+        let res = count_up(2, Arc::default());
+        assert!(res.is_loading());
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(!res.is_loading());
+        res.take_data();
+        assert!(res.is_loading());
+
+        sleep(Duration::from_millis(50)).await;
+        res.take_data();
+        sleep(Duration::from_millis(10)).await;
+        assert!(!res.is_loading());
+    }
+
+    #[tokio::test]
+    async fn is_empty() {
+        assert!(StreamResponse::<()>::empty().is_empty());
+
+        let res = count_up(2, Arc::default());
+        assert!(!res.is_empty());
+
+        sleep(Duration::from_millis(50)).await;
+        res.take_data();
+        assert!(!res.is_empty());
+
+        sleep(Duration::from_millis(50)).await;
+        assert!(!res.is_empty());
+        res.take_data();
+        sleep(Duration::from_millis(10)).await;
+        assert!(res.is_empty());
+    }
+
+    #[tokio::test]
+    async fn wait_until_taken() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let res = count_up(10, Arc::clone(&sent));
+
+        // One value is in the channel and the next one is waiting to be sent
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(1, sent.load(Ordering::SeqCst));
+        res.take_data();
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(2, sent.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn cancel_on_drop() {
+        let sent = Arc::new(AtomicUsize::new(0));
+        drop(count_up(10, Arc::clone(&sent)));
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(0, sent.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,426 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use atrium_api::xrpc::{
+    http::{header, HeaderMap, Method, Request, Response, StatusCode},
+    HttpClient,
+};
+
+use super::client::SharedHttpClient;
+
+/// Retries requests that failed for transient reasons, waiting for the rate limit if exceeded.
+///
+/// Only requests rejected by the rate limit are retried if they are not idempotent,
+/// since the server has not processed them.
+pub struct RetryClient {
+    inner: SharedHttpClient,
+    policy: RetryPolicy,
+    rate_limit: RateLimit,
+}
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled for each retry.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Gives up instead of waiting longer than this for the server.
+    pub max_wait: Duration,
+    /// Retries immediately if `false`, e.g. when the responses are replayed.
+    pub wait: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+            // Shorter than the default deadline of requests
+            max_wait: Duration::from_secs(30),
+            wait: true,
+        }
+    }
+}
+
+/// The quota reported by the `ratelimit-*` headers, shared with the UI.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimit(Arc<RwLock<Quota>>);
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Quota {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// When the remaining quota is reset.
+    pub reset: Option<SystemTime>,
+    /// Set while a request is waiting for the rate limit.
+    pub waiting_until: Option<SystemTime>,
+}
+
+impl Quota {
+    /// Returns how long requests will wait for the rate limit.
+    pub fn waiting_for(&self) -> Option<Duration> {
+        let until = self.waiting_until?;
+        Some(until.duration_since(SystemTime::now()).unwrap_or_default())
+    }
+}
+
+impl RateLimit {
+    pub fn get(&self) -> Quota {
+        self.0.read().unwrap().clone()
+    }
+
+    fn update(&self, headers: &HeaderMap) {
+        let limit = header_u64(headers, "ratelimit-limit");
+        let remaining = header_u64(headers, "ratelimit-remaining");
+        let reset = header_u64(headers, "ratelimit-reset");
+        if limit.is_none() && remaining.is_none() && reset.is_none() {
+            return;
+        }
+        let mut quota = self.0.write().unwrap();
+        quota.limit = limit;
+        quota.remaining = remaining;
+        quota.reset = reset.map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+    }
+
+    fn set_waiting_until(&self, until: Option<SystemTime>) {
+        self.0.write().unwrap().waiting_until = until;
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Returns the delay requested by `Retry-After` in seconds,
+/// or until `ratelimit-reset` if the rate limit has been exceeded.
+fn retry_after(response: &Response<Vec<u8>>) -> Option<Duration> {
+    let headers = response.headers();
+    if let Some(secs) = header_u64(headers, header::RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(secs));
+    }
+    // The reset time is sent with every response, not only when the quota is used up
+    let exceeded = response.status() == StatusCode::TOO_MANY_REQUESTS
+        || header_u64(headers, "ratelimit-remaining") == Some(0);
+    if !exceeded {
+        return None;
+    }
+    let reset =
+        SystemTime::UNIX_EPOCH + Duration::from_secs(header_u64(headers, "ratelimit-reset")?);
+    Some(reset.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+impl RetryClient {
+    pub fn new(inner: SharedHttpClient) -> Self {
+        Self {
+            inner,
+            policy: RetryPolicy::default(),
+            rate_limit: RateLimit::default(),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn rate_limit(&self) -> RateLimit {
+        self.rate_limit.clone()
+    }
+
+    fn backoff(&self, retries: u32) -> Duration {
+        self.policy
+            .base_delay
+            .saturating_mul(2_u32.saturating_pow(retries))
+            .min(self.policy.max_delay)
+    }
+
+    /// Returns the delay before retrying, or `None` if the result should be returned.
+    fn retry_delay<E>(
+        &self,
+        method: &Method,
+        result: &Result<Response<Vec<u8>>, E>,
+        retries: u32,
+    ) -> Option<Duration> {
+        if retries >= self.policy.max_retries {
+            return None;
+        }
+        let idempotent = matches!(*method, Method::GET | Method::HEAD);
+        let delay = match result {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                retry_after(response).unwrap_or(self.backoff(retries))
+            }
+            Ok(response) if idempotent && is_transient(response.status()) => {
+                retry_after(response).unwrap_or(self.backoff(retries))
+            }
+            Err(_) if idempotent => self.backoff(retries),
+            _ => return None,
+        };
+        (delay <= self.policy.max_wait).then_some(delay)
+    }
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+fn clone_request(request: &Request<Vec<u8>>) -> Request<Vec<u8>> {
+    let mut clone = Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+#[async_trait]
+impl HttpClient for RetryClient {
+    async fn send_http(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<Response<Vec<u8>>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+        let mut retries = 0;
+        loop {
+            let result = self.inner.send_http(clone_request(&request)).await;
+            if let Ok(response) = &result {
+                self.rate_limit.update(response.headers());
+            }
+            let Some(delay) = self.retry_delay(request.method(), &result, retries) else {
+                return result;
+            };
+            let rate_limited = result
+                .as_ref()
+                .is_ok_and(|r| r.status() == StatusCode::TOO_MANY_REQUESTS);
+            match &result {
+                Ok(response) => tracing::warn!(
+                    "{} {} failed with {}, retry in {delay:?}",
+                    request.method(),
+                    request.uri(),
+                    response.status()
+                ),
+                Err(err) => tracing::warn!(
+                    "{} {} failed: {err}, retry in {delay:?}",
+                    request.method(),
+                    request.uri()
+                ),
+            }
+            if self.policy.wait {
+                if rate_limited {
+                    self.rate_limit
+                        .set_waiting_until(Some(SystemTime::now() + delay));
+                }
+                tokio::time::sleep(delay).await;
+                if rate_limited {
+                    self.rate_limit.set_waiting_until(None);
+                }
+            }
+            retries += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Instant,
+    };
+
+    use super::*;
+    use crate::atp::{client, test_server::TestServer};
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(40),
+            max_wait: Duration::from_secs(5),
+            wait: true,
+        }
+    }
+
+    /// Starts a server responding with `failure` to the first `failures` requests.
+    async fn server(
+        failures: usize,
+        failure: impl Fn() -> Response<Vec<u8>> + Send + Sync + 'static,
+    ) -> (TestServer, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let server = TestServer::start({
+            let count = Arc::clone(&count);
+            move |_| {
+                if count.fetch_add(1, Ordering::SeqCst) < failures {
+                    failure()
+                } else {
+                    Response::builder()
+                        .header("ratelimit-limit", "3000")
+                        .header("ratelimit-remaining", "2999")
+                        .header("ratelimit-reset", "1720000000")
+                        .body(b"{}".to_vec())
+                        .unwrap()
+                }
+            }
+        })
+        .await;
+        (server, count)
+    }
+
+    fn status(status: StatusCode) -> Response<Vec<u8>> {
+        Response::builder().status(status).body(Vec::new()).unwrap()
+    }
+
+    fn request(method: Method, server: &TestServer) -> Request<Vec<u8>> {
+        Request::builder()
+            .method(method)
+            .uri(format!("{}/xrpc/app.bsky.feed.getTimeline", server.url()))
+            .body(Vec::new())
+            .unwrap()
+    }
+
+    fn client() -> RetryClient {
+        RetryClient::new(client::http_client().unwrap()).with_policy(policy())
+    }
+
+    #[tokio::test]
+    async fn retry_transient_failures() {
+        let (server, count) = server(2, || status(StatusCode::BAD_GATEWAY)).await;
+        let client = client();
+        let response = client
+            .send_http(request(Method::GET, &server))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(3, count.load(Ordering::SeqCst));
+
+        let quota = client.rate_limit().get();
+        assert_eq!(Some(3000), quota.limit);
+        assert_eq!(Some(2999), quota.remaining);
+        assert_eq!(None, quota.waiting_until);
+    }
+
+    #[tokio::test]
+    async fn ignore_reset_of_transient_failures() {
+        let (server, count) = server(1, || {
+            let reset = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                + Duration::from_secs(3600);
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .header("ratelimit-remaining", "2999")
+                .header("ratelimit-reset", reset.as_secs().to_string())
+                .body(Vec::new())
+                .unwrap()
+        })
+        .await;
+        let start = Instant::now();
+        let response = client()
+            .send_http(request(Method::GET, &server))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(2, count.load(Ordering::SeqCst));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn give_up() {
+        let (server, count) = server(usize::MAX, || status(StatusCode::SERVICE_UNAVAILABLE)).await;
+        let response = client()
+            .send_http(request(Method::GET, &server))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_eq!(4, count.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn do_not_retry_post() {
+        let (server, count) = server(1, || status(StatusCode::BAD_GATEWAY)).await;
+        let response = client()
+            .send_http(request(Method::POST, &server))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::BAD_GATEWAY, response.status());
+        assert_eq!(1, count.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn retry_after() {
+        let (server, count) = server(1, || {
+            Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(header::RETRY_AFTER, "1")
+                .body(Vec::new())
+                .unwrap()
+        })
+        .await;
+        let client = client();
+        let rate_limit = client.rate_limit();
+        let task = tokio::spawn(async move {
+            let start = Instant::now();
+            let response = client.send_http(request(Method::POST, &server)).await;
+            (start.elapsed(), response.unwrap().status())
+        });
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(rate_limit.get().waiting_for().is_some());
+        let (elapsed, status) = task.await.unwrap();
+        assert_eq!(StatusCode::OK, status);
+        assert!(elapsed >= Duration::from_secs(1));
+        assert_eq!(2, count.load(Ordering::SeqCst));
+        assert_eq!(None, rate_limit.get().waiting_for());
+    }
+
+    #[tokio::test]
+    async fn wait_for_reset() {
+        let (server, _) = server(1, || {
+            let reset = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                + Duration::from_secs(2);
+            Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header("ratelimit-remaining", "0")
+                .header("ratelimit-reset", reset.as_secs().to_string())
+                .body(Vec::new())
+                .unwrap()
+        })
+        .await;
+        let start = Instant::now();
+        let response = client()
+            .send_http(request(Method::GET, &server))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        // The reset time is truncated to seconds
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn do_not_wait_too_long() {
+        let (server, count) = server(1, || {
+            Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(header::RETRY_AFTER, "3600")
+                .body(Vec::new())
+                .unwrap()
+        })
+        .await;
+        let response = client()
+            .send_http(request(Method::GET, &server))
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!(1, count.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,263 @@
+//! Detects mentions, links and hashtags in the text of a post.
+
+use std::ops::Range;
+
+use atrium_api::{
+    app::bsky::richtext::facet,
+    client::Service,
+    com::atproto::identity::resolve_handle,
+    types::{string::Handle, Union},
+    xrpc::XrpcClient,
+};
+#[cfg(test)]
+use rstest::rstest;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The maximum length of a hashtag in graphemes, excluding `#`.
+const MAX_TAG_GRAPHEMES: usize = 64;
+
+/// A feature found in the text, not resolved yet.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Feature {
+    Mention(Handle),
+    Link(String),
+    /// A hashtag without `#`.
+    Tag(String),
+}
+
+/// Returns the facets of `text`, resolving the mentioned handles to DIDs.
+/// Mentions of handles that cannot be resolved are left as plain text.
+pub async fn facets<T>(api: &Service<T>, text: &str) -> Vec<facet::Main>
+where
+    T: XrpcClient + Send + Sync,
+{
+    let mut facets = Vec::new();
+    for (range, feature) in detect(text) {
+        let feature = match feature {
+            Feature::Mention(handle) => {
+                let did = match api
+                    .com
+                    .atproto
+                    .identity
+                    .resolve_handle(resolve_handle::Parameters {
+                        handle: handle.clone(),
+                    })
+                    .await
+                {
+                    Ok(output) => output.did,
+                    Err(err) => {
+                        tracing::warn!("failed to resolve @{}: {err}", handle.as_str());
+                        continue;
+                    }
+                };
+                facet::MainFeaturesItem::Mention(Box::new(facet::Mention { did }))
+            }
+            Feature::Link(uri) => facet::MainFeaturesItem::Link(Box::new(facet::Link { uri })),
+            Feature::Tag(tag) => facet::MainFeaturesItem::Tag(Box::new(facet::Tag { tag })),
+        };
+        facets.push(facet::Main {
+            features: vec![Union::Refs(feature)],
+            index: facet::ByteSlice {
+                byte_start: range.start,
+                byte_end: range.end,
+            },
+        });
+    }
+    facets
+}
+
+/// Finds the features in `text` with their byte ranges.
+pub fn detect(text: &str) -> Vec<(Range<usize>, Feature)> {
+    let mut features = Vec::new();
+    for (start, word) in words(text) {
+        if let Some((len, tag)) = detect_tag(word) {
+            features.push((start..start + len, Feature::Tag(tag)));
+            continue;
+        }
+        // Mentions and links may be enclosed in parentheses
+        let (start, word) = match word.strip_prefix('(') {
+            Some(word) => (start + 1, word),
+            None => (start, word),
+        };
+        if let Some((len, handle)) = detect_mention(word) {
+            features.push((start..start + len, Feature::Mention(handle)));
+        } else if let Some(url) = detect_link(word) {
+            features.push((start..start + url.len(), Feature::Link(url.to_string())));
+        }
+    }
+    features
+}
+
+/// Splits `text` by whitespace, returning the words with their byte offsets.
+fn words(text: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (start, c.is_whitespace()) {
+            (Some(s), true) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            (None, false) => start = Some(i),
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Returns the length of the mention including `@`, and the handle.
+fn detect_mention(word: &str) -> Option<(usize, Handle)> {
+    let handle = word
+        .strip_prefix('@')?
+        .trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+    // Handles without a TLD are not worth resolving
+    if !handle.contains('.') {
+        return None;
+    }
+    let parsed = handle.parse().ok()?;
+    Some(('@'.len_utf8() + handle.len(), parsed))
+}
+
+fn detect_link(word: &str) -> Option<&str> {
+    let rest = word
+        .strip_prefix("https://")
+        .or_else(|| word.strip_prefix("http://"))?;
+    if rest.is_empty() {
+        return None;
+    }
+    let mut url = word;
+    loop {
+        if let Some(trimmed) = url.strip_suffix(['.', ',', ';', ':', '!', '?', '"', '\'']) {
+            url = trimmed;
+        } else if url.ends_with(')') && url.matches('(').count() < url.matches(')').count() {
+            // Keep the closing parenthesis only if it is a part of the URL
+            url = &url[..url.len() - 1];
+        } else {
+            break;
+        }
+    }
+    Some(url).filter(|url| url.len() > word.len() - rest.len())
+}
+
+/// Returns the length of the hashtag including `#`, and the tag.
+fn detect_tag(word: &str) -> Option<(usize, String)> {
+    let (prefix, rest) = word
+        .strip_prefix('#')
+        .map(|rest| ('#', rest))
+        .or_else(|| word.strip_prefix('＃').map(|rest| ('＃', rest)))?;
+    // Not a tag but the keycap emoji "#️⃣"
+    if rest.starts_with(['\u{fe0f}', '\u{20e3}']) {
+        return None;
+    }
+    let tag = rest.trim_end_matches(|c: char| {
+        c.is_ascii_punctuation() || matches!(c, '、' | '。' | '，' | '！' | '？' | '」' | '』')
+    });
+    if tag.is_empty()
+        || tag.chars().all(|c| c.is_ascii_digit())
+        || tag.graphemes(true).count() > MAX_TAG_GRAPHEMES
+    {
+        return None;
+    }
+    Some((prefix.len_utf8() + tag.len(), tag.to_string()))
+}
+
+#[cfg(test)]
+fn mention(handle: &str) -> Feature {
+    Feature::Mention(handle.parse().unwrap())
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(vec![], "")]
+#[case(vec![], "no features here")]
+#[case(vec![(0..11, mention("alice.test"))], "@alice.test")]
+#[case(vec![(3..14, mention("alice.test"))], "hi @alice.test, how are you?")]
+#[case(vec![(1..12, mention("alice.test"))], "(@alice.test)")]
+#[case(vec![], "email@alice.test")]
+#[case(vec![], "@alice")]
+#[case(vec![(19..30, mention("alice.test"))], "こんにちは、 @alice.test さん")]
+#[case(vec![(5..24, Feature::Link(String::from("https://example.com")))], "see: https://example.com.")]
+#[case(
+    vec![(1..35, Feature::Link(String::from("https://en.wikipedia.org/wiki/Rust")))],
+    "(https://en.wikipedia.org/wiki/Rust)"
+)]
+#[case(
+    vec![(0..38, Feature::Link(String::from("https://en.wikipedia.org/wiki/Rust_(x)")))],
+    "https://en.wikipedia.org/wiki/Rust_(x)"
+)]
+#[case(vec![], "https://")]
+#[case(vec![], "ftp://example.com")]
+#[case(vec![(0..5, Feature::Tag(String::from("rust")))], "#rust!")]
+#[case(vec![(7..14, Feature::Tag(String::from("日本")))], "I 💙 #日本")]
+#[case(vec![(16..25, Feature::Tag(String::from("日本")))], "桜が咲いた ＃日本。")]
+#[case(vec![], "#123")]
+#[case(vec![], "#️⃣")]
+#[case(vec![], "issue#1")]
+#[case(vec![(0..6, Feature::Tag(String::from("2024a")))], "#2024a")]
+#[case(
+    vec![
+        (9..20, mention("alice.test")),
+        (40..45, Feature::Tag(String::from("🦀"))),
+        (46..65, Feature::Link(String::from("https://example.com"))),
+    ],
+    "👋🏻 @alice.test 👨‍👩‍👧 #🦀 https://example.com"
+)]
+fn test_detect(#[case] expected: Vec<(Range<usize>, Feature)>, #[case] text: &str) {
+    let features = detect(text);
+    for (range, _) in &features {
+        // The offsets are on char boundaries
+        assert!(text.get(range.clone()).is_some());
+    }
+    assert_eq!(expected, features);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use atrium_api::{client::AtpServiceClient, xrpc::http::StatusCode};
+
+    use super::*;
+    use crate::atp::{client::Client, fixture::FixtureClient};
+
+    fn api(client: FixtureClient) -> Service<Client> {
+        AtpServiceClient::new(Client::new(Arc::new(client), "https://pds.example.com")).service
+    }
+
+    #[tokio::test]
+    async fn resolve_mentions() {
+        let api = api(FixtureClient::default());
+        let text = "🌸 @alice.test #bsky";
+        let facets = facets(&api, text).await;
+
+        assert_eq!(2, facets.len());
+        assert_eq!("@alice.test", &text[5..16]);
+        assert_eq!(
+            facet::Main {
+                features: vec![Union::Refs(facet::MainFeaturesItem::Mention(Box::new(
+                    facet::Mention {
+                        did: "did:plc:alice".parse().unwrap()
+                    }
+                )))],
+                index: facet::ByteSlice {
+                    byte_start: 5,
+                    byte_end: 16,
+                },
+            },
+            facets[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_unknown_handles() {
+        let api = api(FixtureClient::default().xrpc_error(
+            "com.atproto.identity.resolveHandle",
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+        ));
+        let facets = facets(&api, "@nobody.test https://example.com").await;
+
+        assert_eq!(1, facets.len());
+        assert_eq!(13, facets[0].index.byte_start);
+    }
+}
@@ -1,50 +1,289 @@
-use std::path::PathBuf;
+mod encrypted;
 
-use anyhow::{bail, Result};
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use atrium_api::agent::{store::SessionStore, Session};
-use tokio::fs;
+use atrium_api::{
+    agent::{store::SessionStore, Session},
+    types::string::Did,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
 use tracing::instrument;
 
+pub use self::encrypted::EncryptedStore;
+use self::encrypted::{Encryption, Sealed};
+
+/// A [`SessionStore`] that keeps the sessions of all accounts in a single file.
+///
+/// Each store is bound to the account of the session most recently set to it.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    did: Arc<RwLock<Option<Did>>>,
+    /// Shared by all stores of the same file to serialize read-modify-write cycles.
+    lock: Arc<Mutex<()>>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Sessions {
+    pub active: Option<Did>,
+    pub sessions: BTreeMap<String, StoredSession>,
+    /// Set once the sessions have been encrypted with a passphrase.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<Encryption>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum StoredSession {
+    Plain(Session),
+    Sealed(Sealed),
+}
+
+impl StoredSession {
+    /// Returns the session unless it is encrypted.
+    pub fn session(&self) -> Option<&Session> {
+        match self {
+            Self::Plain(session) => Some(session),
+            Self::Sealed(_) => None,
+        }
+    }
+}
+
+/// The store of one account, encrypted or not.
 #[derive(Clone, Debug)]
-pub struct FileStore(PathBuf);
+pub enum AccountStore {
+    File(FileStore),
+    Encrypted(EncryptedStore),
+}
+
+/// The version of the session file format written by this build.
+///
+/// 0. A single session, written by versions that support only one account.
+/// 1. [`Sessions`] without the version.
+/// 2. [`Sessions`] with the version.
+/// 3. Sessions may be sealed by [`EncryptedStore`].
+const VERSION: u64 = 3;
+
+#[derive(Serialize)]
+struct VersionedSessions<'a> {
+    version: u64,
+    #[serde(flatten)]
+    sessions: &'a Sessions,
+}
+
+#[derive(Debug)]
+struct NewerVersion(u64);
+
+impl std::fmt::Display for NewerVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the session file was written by a newer version (v{})",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for NewerVersion {}
+
+/// Upgrades the contents of a session file to the current version.
+fn migrate(mut value: serde_json::Value) -> Result<Sessions> {
+    let object = value.as_object_mut().context("not a JSON object")?;
+    let mut version = match object.remove("version") {
+        Some(version) => version.as_u64().context("invalid version")?,
+        None if object.contains_key("sessions") => 1,
+        None => 0,
+    };
+    if version > VERSION {
+        return Err(NewerVersion(version).into());
+    }
+    while version < VERSION {
+        value = match version {
+            0 => {
+                let session: Session = serde_json::from_value(value)?;
+                serde_json::to_value(Sessions {
+                    active: Some(session.did.clone()),
+                    sessions: BTreeMap::from([(
+                        session.did.to_string(),
+                        StoredSession::Plain(session),
+                    )]),
+                    encryption: None,
+                })?
+            }
+            // Only the version field has been added
+            1 => value,
+            // Only sealed sessions have been added
+            2 => value,
+            _ => unreachable!(),
+        };
+        version += 1;
+    }
+    Ok(serde_json::from_value(value)?)
+}
 
 impl FileStore {
     pub fn new() -> Result<Self> {
-        Ok(Self(crate::utils::local_data_dir()?.join("session.json")))
+        Ok(Self::with_path(
+            crate::utils::local_data_dir()?.join("session.json"),
+        ))
     }
 
-    async fn open_file(&self) -> Result<std::fs::File> {
-        let file = fs::File::options()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(&self.0)
-            .await?;
-        Ok(file.into_std().await)
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            path,
+            did: Arc::new(RwLock::new(None)),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Creates a store of the same file bound to `did`, or to the next account signing in.
+    pub fn account(&self, did: Option<Did>) -> Self {
+        Self {
+            path: self.path.clone(),
+            did: Arc::new(RwLock::new(did)),
+            lock: Arc::clone(&self.lock),
+        }
+    }
+
+    pub fn did(&self) -> Option<Did> {
+        self.did.read().unwrap().clone()
+    }
+
+    #[instrument(name = "load_sessions", err)]
+    pub async fn load(&self) -> Result<Sessions> {
+        let _lock = self.lock.lock().await;
+        self.read().await
+    }
+
+    #[instrument(name = "set_active_account", err)]
+    pub async fn set_active(&self) -> Result<()> {
+        let did = self.did().context("no account is bound to the store")?;
+        self.update(|sessions| {
+            sessions.active = Some(did);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Copies the sessions to `path` without their tokens, dropping encrypted ones.
+    #[instrument(name = "export_sessions", err)]
+    pub async fn export(&self, path: PathBuf) -> Result<()> {
+        let mut sessions = self.load().await?;
+        sessions.encryption = None;
+        sessions.sessions.retain(|_, session| {
+            let StoredSession::Plain(session) = session else {
+                return false;
+            };
+            session.access_jwt = String::from("<redacted>");
+            session.refresh_jwt = String::from("<redacted>");
+            true
+        });
+        Self::with_path(path).write(&sessions).await
+    }
+
+    /// Reads, modifies, and writes the file without being interleaved with other stores.
+    async fn update<T>(&self, f: impl FnOnce(&mut Sessions) -> Result<T>) -> Result<T> {
+        let _lock = self.lock.lock().await;
+        let mut sessions = self.read().await?;
+        let output = f(&mut sessions)?;
+        self.write(&sessions).await?;
+        Ok(output)
+    }
+
+    async fn read(&self) -> Result<Sessions> {
+        if !fs::try_exists(&self.path).await? {
+            return Ok(Sessions::default());
+        }
+        let bytes = fs::read(&self.path).await?;
+        match serde_json::from_slice(&bytes)
+            .map_err(anyhow::Error::from)
+            .and_then(migrate)
+        {
+            Ok(sessions) => Ok(sessions),
+            // Never overwrite sessions that this version cannot understand
+            Err(err) if err.is::<NewerVersion>() => Err(err),
+            Err(err) => {
+                // Keep the broken file for investigation instead of failing forever
+                let backup = self.path.with_extension("json.corrupt");
+                tracing::warn!("the session file is corrupt, move it to {backup:?}: {err:#}");
+                fs::rename(&self.path, &backup).await?;
+                Ok(Sessions::default())
+            }
+        }
+    }
+
+    /// Replaces the file atomically so that a crash never leaves a partial file.
+    async fn write(&self, sessions: &Sessions) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        fs::remove_file(&tmp).await.ok();
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp).await?;
+        let contents = VersionedSessions {
+            version: VERSION,
+            sessions,
+        };
+        file.write_all(&serde_json::to_vec(&contents)?).await?;
+        file.sync_all().await?;
+        fs::rename(&tmp, &self.path).await?;
+        Ok(())
     }
 
     #[instrument(name = "get_session", err)]
     async fn get(&self) -> Result<Session> {
-        if fs::try_exists(&self.0).await? {
-            let session = serde_json::from_reader(self.open_file().await?)?;
-            Ok(session)
-        } else {
-            bail!("file not found");
+        match self.get_stored().await? {
+            StoredSession::Plain(session) => Ok(session),
+            StoredSession::Sealed(_) => bail!("the session is encrypted, use --encrypt-session"),
+        }
+    }
+
+    async fn get_stored(&self) -> Result<StoredSession> {
+        let did = self.did().context("no account is bound to the store")?;
+        let _lock = self.lock.lock().await;
+        match self.read().await?.sessions.remove(did.as_str()) {
+            Some(session) => Ok(session),
+            None => bail!("session not found"),
         }
     }
 
     #[instrument(name = "set_session", err, skip(session))]
     async fn set(&self, session: Session) -> Result<()> {
-        serde_json::to_writer(self.open_file().await?, &session)?;
-        Ok(())
+        let did = session.did.clone();
+        self.set_stored(did, StoredSession::Plain(session)).await
+    }
+
+    async fn set_stored(&self, did: Did, session: StoredSession) -> Result<()> {
+        // The agent has signed in to another account if the DID differs.
+        *self.did.write().unwrap() = Some(did.clone());
+        self.update(|sessions| {
+            sessions.sessions.insert(did.to_string(), session);
+            Ok(())
+        })
+        .await
     }
 
     #[instrument(name = "clear_session", err)]
     async fn clear(&self) -> Result<()> {
-        fs::remove_file(&self.0).await?;
-        Ok(())
+        let Some(did) = self.did() else {
+            return Ok(());
+        };
+        self.update(|sessions| {
+            sessions.sessions.remove(did.as_str());
+            if sessions.active.as_ref() == Some(&did) {
+                sessions.active = None;
+            }
+            Ok(())
+        })
+        .await
     }
 }
 
@@ -62,3 +301,254 @@ impl SessionStore for FileStore {
         self.clear().await.ok();
     }
 }
+
+impl AccountStore {
+    /// Creates a store of the same file bound to `did`, or to the next account signing in.
+    pub fn account(&self, did: Option<Did>) -> Self {
+        match self {
+            Self::File(store) => Self::File(store.account(did)),
+            Self::Encrypted(store) => Self::Encrypted(store.account(did)),
+        }
+    }
+
+    fn file(&self) -> &FileStore {
+        match self {
+            Self::File(store) => store,
+            Self::Encrypted(store) => store.file(),
+        }
+    }
+
+    pub fn did(&self) -> Option<Did> {
+        self.file().did()
+    }
+
+    pub async fn load(&self) -> Result<Sessions> {
+        self.file().load().await
+    }
+
+    pub async fn set_active(&self) -> Result<()> {
+        self.file().set_active().await
+    }
+
+    pub async fn export(&self, path: PathBuf) -> Result<()> {
+        self.file().export(path).await
+    }
+
+    /// Returns `true` until the passphrase is entered if the sessions are encrypted.
+    pub fn is_locked(&self) -> bool {
+        match self {
+            Self::File(_) => false,
+            Self::Encrypted(store) => !store.is_unlocked(),
+        }
+    }
+
+    pub async fn unlock(&self, passphrase: String) -> Result<()> {
+        match self {
+            Self::File(_) => Ok(()),
+            Self::Encrypted(store) => store.unlock(passphrase).await,
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for AccountStore {
+    async fn get_session(&self) -> Option<Session> {
+        match self {
+            Self::File(store) => store.get_session().await,
+            Self::Encrypted(store) => store.get_session().await,
+        }
+    }
+
+    async fn set_session(&self, session: Session) {
+        match self {
+            Self::File(store) => store.set_session(session).await,
+            Self::Encrypted(store) => store.set_session(session).await,
+        }
+    }
+
+    async fn clear_session(&self) {
+        match self {
+            Self::File(store) => store.clear_session().await,
+            Self::Encrypted(store) => store.clear_session().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(did: &str, handle: &str) -> Session {
+        Session {
+            access_jwt: String::from("access"),
+            did: did.parse().unwrap(),
+            did_doc: None,
+            email: None,
+            email_auth_factor: None,
+            email_confirmed: None,
+            handle: handle.parse().unwrap(),
+            refresh_jwt: String::from("refresh"),
+        }
+    }
+
+    fn store() -> (tempfile::TempDir, FileStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::with_path(dir.path().join("session.json"));
+        (dir, store)
+    }
+
+    #[tokio::test]
+    async fn accounts() {
+        let (_dir, store) = store();
+        let alice = store.account(None);
+        let bob = store.account(None);
+        alice
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        bob.set(session("did:plc:bob", "bob.test")).await.unwrap();
+        bob.set_active().await.unwrap();
+
+        assert_eq!("alice.test", alice.get().await.unwrap().handle.as_str());
+        assert_eq!("bob.test", bob.get().await.unwrap().handle.as_str());
+        let sessions = store.load().await.unwrap();
+        assert_eq!(Some("did:plc:bob"), sessions.active.as_deref());
+        assert_eq!(2, sessions.sessions.len());
+
+        bob.clear().await.unwrap();
+        let sessions = store.load().await.unwrap();
+        assert_eq!(None, sessions.active);
+        assert_eq!(1, sessions.sessions.len());
+    }
+
+    #[tokio::test]
+    async fn shorter_session() {
+        let (_dir, store) = store();
+        let mut long = session("did:plc:alice", "alice.test");
+        long.access_jwt = "a".repeat(1000);
+        store.set(long).await.unwrap();
+        store
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        assert_eq!("access", store.get().await.unwrap().access_jwt);
+    }
+
+    #[tokio::test]
+    async fn versioned() {
+        let (_dir, store) = store();
+        store
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        let value: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&store.path).unwrap()).unwrap();
+        assert_eq!(Some(VERSION), value["version"].as_u64());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_dir, store) = store();
+        std::fs::write(&store.path, "{}").unwrap();
+        std::fs::set_permissions(&store.path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        store
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        let mode = std::fs::metadata(&store.path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    #[tokio::test]
+    async fn migrate_single_session() {
+        let (_dir, store) = store();
+        let legacy = session("did:plc:alice", "alice.test");
+        std::fs::write(&store.path, serde_json::to_vec(&legacy).unwrap()).unwrap();
+        let sessions = store.load().await.unwrap();
+        assert_eq!(Some("did:plc:alice"), sessions.active.as_deref());
+        assert_eq!(
+            Some(&legacy),
+            sessions
+                .sessions
+                .get("did:plc:alice")
+                .and_then(StoredSession::session)
+        );
+    }
+
+    #[tokio::test]
+    async fn migrate_unversioned() {
+        let (_dir, store) = store();
+        let sessions = Sessions {
+            active: None,
+            sessions: BTreeMap::from([(
+                String::from("did:plc:alice"),
+                StoredSession::Plain(session("did:plc:alice", "alice.test")),
+            )]),
+            encryption: None,
+        };
+        std::fs::write(&store.path, serde_json::to_vec(&sessions).unwrap()).unwrap();
+        assert_eq!(1, store.load().await.unwrap().sessions.len());
+    }
+
+    #[tokio::test]
+    async fn newer_version() {
+        let (_dir, store) = store();
+        let contents = format!(r#"{{"version":{},"sessions":{{}}}}"#, VERSION + 1);
+        std::fs::write(&store.path, &contents).unwrap();
+        assert!(store.load().await.is_err());
+        assert!(store
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .is_err());
+        assert_eq!(contents, std::fs::read_to_string(&store.path).unwrap());
+    }
+
+    #[tokio::test]
+    async fn export() {
+        let (dir, store) = store();
+        store
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        let path = dir.path().join("exported.json");
+        store.export(path.clone()).await.unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(r#""access""#));
+        assert!(!contents.contains(r#""refresh""#));
+        let exported = FileStore::with_path(path).account(store.did());
+        assert_eq!("alice.test", exported.get().await.unwrap().handle.as_str());
+    }
+
+    #[tokio::test]
+    async fn corrupt_file() {
+        let (_dir, store) = store();
+        std::fs::write(&store.path, b"\0\xff garbage").unwrap();
+        let alice = store.account(Some("did:plc:alice".parse().unwrap()));
+        assert_eq!(None, alice.get_session().await);
+
+        alice
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        assert!(alice.get().await.is_ok());
+        assert!(store.path.with_extension("json.corrupt").exists());
+    }
+
+    #[tokio::test]
+    async fn partial_file() {
+        let (_dir, store) = store();
+        store
+            .set(session("did:plc:alice", "alice.test"))
+            .await
+            .unwrap();
+        let contents = std::fs::read(&store.path).unwrap();
+        std::fs::write(&store.path, &contents[..contents.len() / 2]).unwrap();
+
+        assert_eq!(None, store.get_session().await);
+        assert!(store.load().await.unwrap().sessions.is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use async_trait::async_trait;
+use atrium_api::{
+    agent::{store::SessionStore, Session},
+    types::string::Did,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use tokio::task;
+use tracing::instrument;
+
+use super::{FileStore, StoredSession};
+
+/// A [`SessionStore`] that encrypts the sessions in a [`FileStore`] with a passphrase.
+///
+/// Sessions can neither be read nor written until the store is unlocked.
+#[derive(Clone, Debug)]
+pub struct EncryptedStore {
+    file: FileStore,
+    /// Shared by all stores of the same file so that they are unlocked at once.
+    cipher: Arc<RwLock<Option<Arc<Cipher>>>>,
+}
+
+/// The parameters to derive the key, stored next to the sessions.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Encryption {
+    salt: String,
+    /// A known text sealed with the key to tell if a passphrase is correct.
+    check: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct Sealed {
+    sealed: String,
+}
+
+const CHECK: &[u8] = b"termsky";
+
+impl EncryptedStore {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_file(FileStore::new()?))
+    }
+
+    fn with_file(file: FileStore) -> Self {
+        Self {
+            file,
+            cipher: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Creates a store of the same file bound to `did`, or to the next account signing in.
+    pub fn account(&self, did: Option<Did>) -> Self {
+        Self {
+            file: self.file.account(did),
+            cipher: Arc::clone(&self.cipher),
+        }
+    }
+
+    pub(super) fn file(&self) -> &FileStore {
+        &self.file
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.cipher.read().unwrap().is_some()
+    }
+
+    fn cipher(&self) -> Result<Arc<Cipher>> {
+        match &*self.cipher.read().unwrap() {
+            Some(cipher) => Ok(Arc::clone(cipher)),
+            None => bail!("the session store is locked"),
+        }
+    }
+
+    /// Derives the key from `passphrase`, or sets the passphrase if none has been set yet.
+    ///
+    /// Sessions stored in plaintext are encrypted with the key.
+    #[instrument(name = "unlock_sessions", err, skip_all)]
+    pub async fn unlock(&self, passphrase: String) -> Result<()> {
+        let salt = match self.file.load().await?.encryption {
+            Some(encryption) => STANDARD.decode(&encryption.salt)?,
+            None => {
+                let mut salt = vec![0; 16];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        };
+        let encoded_salt = STANDARD.encode(&salt);
+        // Argon2id takes a while on purpose, so it must not hold up the other tasks
+        let cipher = task::spawn_blocking(move || Cipher::derive(&passphrase, &salt)).await??;
+        let cipher = self
+            .file
+            .update(|sessions| {
+                match &sessions.encryption {
+                    Some(encryption) if encryption.salt != encoded_salt => {
+                        bail!("the passphrase has been set while unlocking")
+                    }
+                    Some(encryption) => {
+                        if cipher.open(&encryption.check, b"check").is_err() {
+                            bail!("wrong passphrase");
+                        }
+                    }
+                    None => {
+                        sessions.encryption = Some(Encryption {
+                            salt: encoded_salt,
+                            check: cipher.seal(CHECK, b"check")?,
+                        });
+                    }
+                }
+                for (did, session) in &mut sessions.sessions {
+                    if let StoredSession::Plain(plain) = session {
+                        let sealed = cipher.seal(&serde_json::to_vec(plain)?, did.as_bytes())?;
+                        *session = StoredSession::Sealed(Sealed { sealed });
+                    }
+                }
+                Ok(cipher)
+            })
+            .await?;
+        *self.cipher.write().unwrap() = Some(Arc::new(cipher));
+        Ok(())
+    }
+
+    #[instrument(name = "get_encrypted_session", err)]
+    async fn get(&self) -> Result<Session> {
+        let did = self
+            .file
+            .did()
+            .context("no account is bound to the store")?;
+        match self.file.get_stored().await? {
+            StoredSession::Plain(session) => Ok(session),
+            StoredSession::Sealed(sealed) => {
+                let plain = self.cipher()?.open(&sealed.sealed, did.as_bytes())?;
+                Ok(serde_json::from_slice(&plain)?)
+            }
+        }
+    }
+
+    #[instrument(name = "set_encrypted_session", err, skip(session))]
+    async fn set(&self, session: Session) -> Result<()> {
+        let did = session.did.clone();
+        let sealed = self
+            .cipher()?
+            .seal(&serde_json::to_vec(&session)?, did.as_bytes())?;
+        self.file
+            .set_stored(did, StoredSession::Sealed(Sealed { sealed }))
+            .await
+    }
+}
+
+#[async_trait]
+impl SessionStore for EncryptedStore {
+    async fn get_session(&self) -> Option<Session> {
+        self.get().await.ok()
+    }
+
+    async fn set_session(&self, session: Session) {
+        self.set(session).await.ok();
+    }
+
+    async fn clear_session(&self) {
+        self.file.clear().await.ok();
+    }
+}
+
+struct Cipher(XChaCha20Poly1305);
+
+impl std::fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cipher(..)")
+    }
+}
+
+impl Cipher {
+    fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        // The default parameters take seconds in unoptimized builds
+        #[cfg(test)]
+        let params = Params::new(Params::MIN_M_COST, 1, 1, None).unwrap();
+        #[cfg(not(test))]
+        let params = Params::default();
+        let mut key = [0; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|err| anyhow!("failed to derive the key: {err}"))?;
+        Ok(Self(XChaCha20Poly1305::new(&key.into())))
+    }
+
+    /// Encrypts `msg` bound to `aad`, and encodes the nonce and the ciphertext.
+    fn seal(&self, msg: &[u8], aad: &[u8]) -> Result<String> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut sealed = nonce.to_vec();
+        sealed.extend(
+            self.0
+                .encrypt(&nonce, Payload { msg, aad })
+                .map_err(|_| anyhow!("failed to encrypt"))?,
+        );
+        Ok(STANDARD.encode(sealed))
+    }
+
+    fn open(&self, sealed: &str, aad: &[u8]) -> Result<Vec<u8>> {
+        let sealed = STANDARD.decode(sealed)?;
+        if sealed.len() < 24 {
+            bail!("the sealed data is too short");
+        }
+        let (nonce, msg) = sealed.split_at(24);
+        self.0
+            .decrypt(XNonce::from_slice(nonce), Payload { msg, aad })
+            .map_err(|_| anyhow!("failed to decrypt"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(did: &str) -> Session {
+        Session {
+            access_jwt: String::from("access"),
+            did: did.parse().unwrap(),
+            did_doc: None,
+            email: None,
+            email_auth_factor: None,
+            email_confirmed: None,
+            handle: "alice.test".parse().unwrap(),
+            refresh_jwt: String::from("refresh"),
+        }
+    }
+
+    fn store() -> (tempfile::TempDir, EncryptedStore) {
+        let dir = tempfile::tempdir().unwrap();
+        let store =
+            EncryptedStore::with_file(FileStore::with_path(dir.path().join("session.json")));
+        (dir, store)
+    }
+
+    #[tokio::test]
+    async fn seal_sessions() {
+        let (_dir, store) = store();
+        assert!(store.set(session("did:plc:alice")).await.is_err());
+
+        store.unlock(String::from("passphrase")).await.unwrap();
+        store.set(session("did:plc:alice")).await.unwrap();
+        let contents = std::fs::read_to_string(&store.file.path).unwrap();
+        assert!(!contents.contains("access"));
+        assert!(!contents.contains("refresh"));
+        assert_eq!(session("did:plc:alice"), store.get().await.unwrap());
+
+        // A new store must be unlocked again
+        let locked = EncryptedStore::with_file(store.file.account(None)).account(store.file.did());
+        assert!(locked.get().await.is_err());
+        assert!(locked.unlock(String::from("wrong")).await.is_err());
+        locked.unlock(String::from("passphrase")).await.unwrap();
+        assert_eq!(session("did:plc:alice"), locked.get().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn seal_existing_sessions() {
+        let (_dir, store) = store();
+        store.file.set(session("did:plc:alice")).await.unwrap();
+        let alice = store.account(store.file.did());
+        alice.unlock(String::from("passphrase")).await.unwrap();
+
+        assert!(!std::fs::read_to_string(&store.file.path)
+            .unwrap()
+            .contains("access"));
+        assert!(store.file.get().await.is_err());
+        assert_eq!(session("did:plc:alice"), alice.get().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn bound_to_account() {
+        let (_dir, store) = store();
+        store.unlock(String::from("passphrase")).await.unwrap();
+        let alice = store.account(None);
+        let bob = store.account(None);
+        alice.set(session("did:plc:alice")).await.unwrap();
+        bob.set(session("did:plc:bob")).await.unwrap();
+
+        // Swap the sealed sessions of the accounts
+        let mut sessions = store.file.load().await.unwrap();
+        let alice_sealed = sessions.sessions.remove("did:plc:alice").unwrap();
+        let bob_sealed = sessions.sessions.remove("did:plc:bob").unwrap();
+        sessions
+            .sessions
+            .insert(String::from("did:plc:alice"), bob_sealed);
+        sessions
+            .sessions
+            .insert(String::from("did:plc:bob"), alice_sealed);
+        store.file.write(&sessions).await.unwrap();
+
+        assert!(alice.get().await.is_err());
+        assert!(bob.get().await.is_err());
+    }
+}
@@ -0,0 +1,84 @@
+//! A minimal HTTP/1.1 server that stands in for remote services in tests.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use atrium_api::xrpc::http::{Request, Response};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+pub struct TestServer {
+    addr: SocketAddr,
+    task: tokio::task::JoinHandle<()>,
+}
+
+type Handler = dyn Fn(Request<Vec<u8>>) -> Response<Vec<u8>> + Send + Sync;
+
+impl TestServer {
+    pub async fn start(
+        handler: impl Fn(Request<Vec<u8>>) -> Response<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handler: Arc<Handler> = Arc::new(handler);
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&handler)));
+            }
+        });
+        Self { addr, task }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(stream: TcpStream, handler: Arc<Handler>) {
+    let mut stream = BufReader::new(stream);
+
+    let mut line = String::new();
+    stream.read_line(&mut line).await.unwrap();
+    let mut parts = line.split_whitespace();
+    let mut builder = Request::builder()
+        .method(parts.next().unwrap())
+        .uri(parts.next().unwrap());
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await.unwrap();
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().unwrap();
+        }
+        builder = builder.header(name, value);
+    }
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await.unwrap();
+
+    let response = handler(builder.body(body).unwrap());
+
+    let mut head = format!("HTTP/1.1 {}\r\n", response.status());
+    for (name, value) in response.headers() {
+        head += &format!("{name}: {}\r\n", value.to_str().unwrap());
+    }
+    head += &format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        response.body().len()
+    );
+    let stream = stream.get_mut();
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(response.body()).await.unwrap();
+    stream.shutdown().await.ok();
+}
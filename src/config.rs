@@ -0,0 +1,131 @@
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{bail, Context, Result};
+#[cfg(test)]
+use rstest::rstest;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// The service used to sign in and to resolve handles.
+    pub service: String,
+    /// The PLC directory used to resolve `did:plc` identifiers.
+    pub plc_directory: String,
+    /// Whether to encrypt the sessions with a passphrase.
+    pub encrypt_session: bool,
+    /// The directory to save the requests and the responses to.
+    pub record: Option<PathBuf>,
+    /// The directory to serve the recorded responses from instead of the network.
+    pub replay: Option<PathBuf>,
+    /// How long to wait for a request, including retries, before giving up.
+    pub timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            service: String::from("https://bsky.social"),
+            plc_directory: String::from("https://plc.directory"),
+            encrypt_session: false,
+            record: None,
+            replay: None,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut config = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .with_context(|| format!("{arg} requires a value"))
+            };
+            match arg.as_str() {
+                "--service" => config.service = normalize_url(value()?),
+                "--plc-directory" => config.plc_directory = normalize_url(value()?),
+                "--encrypt-session" => config.encrypt_session = true,
+                "--record" => config.record = Some(value()?.into()),
+                "--replay" => config.replay = Some(value()?.into()),
+                "--timeout" => {
+                    let secs = value()?.parse().context("--timeout requires seconds")?;
+                    if secs == 0 {
+                        bail!("--timeout must be at least 1 second");
+                    }
+                    config.timeout = Duration::from_secs(secs);
+                }
+                _ => bail!("unknown argument: {arg}"),
+            }
+        }
+        if config.record.is_some() && config.replay.is_some() {
+            bail!("--record and --replay cannot be used together");
+        }
+        Ok(config)
+    }
+}
+
+fn normalize_url(url: String) -> String {
+    let url = url.trim_end_matches('/');
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{url}")
+    }
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(Config::default(), &[])]
+#[case(
+    Config {
+        service: String::from("https://pds.example.com"),
+        ..Default::default()
+    },
+    &["--service", "pds.example.com/"],
+)]
+#[case(
+    Config {
+        service: String::from("http://localhost:2583"),
+        plc_directory: String::from("http://localhost:2582"),
+        ..Default::default()
+    },
+    &["--service", "http://localhost:2583", "--plc-directory", "http://localhost:2582"],
+)]
+#[case(
+    Config {
+        encrypt_session: true,
+        ..Default::default()
+    },
+    &["--encrypt-session"],
+)]
+#[case(
+    Config {
+        record: Some(PathBuf::from("recording")),
+        ..Default::default()
+    },
+    &["--record", "recording"],
+)]
+#[case(
+    Config {
+        timeout: Duration::from_secs(5),
+        ..Default::default()
+    },
+    &["--timeout", "5"],
+)]
+fn parse_args(#[case] config: Config, #[case] args: &[&str]) {
+    let args = args.iter().map(|s| s.to_string());
+    assert_eq!(config, Config::from_args(args).unwrap());
+}
+
+#[cfg(test)]
+#[rstest]
+#[case::missing_value(&["--service"])]
+#[case::unknown(&["--foo"])]
+#[case::invalid_timeout(&["--timeout", "5s"])]
+#[case::zero_timeout(&["--timeout", "0"])]
+#[case::record_and_replay(&["--record", "a", "--replay", "b"])]
+fn parse_invalid_args(#[case] args: &[&str]) {
+    let args = args.iter().map(|s| s.to_string());
+    assert!(Config::from_args(args).is_err());
+}
@@ -1,5 +1,6 @@
 mod app;
 mod atp;
+mod config;
 mod prelude;
 mod tui;
 mod utils;
@@ -7,9 +8,10 @@ mod widgets;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let config = config::Config::from_args(std::env::args().skip(1))?;
     utils::init()?;
     tui::enter()?;
-    app::run().await?;
+    app::run(config).await?;
     tui::exit()?;
     Ok(())
 }
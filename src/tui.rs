@@ -11,13 +11,19 @@ use crossterm::{
 };
 use futures_util::{FutureExt, StreamExt};
 use ratatui::prelude::*;
-use tokio::{sync::mpsc, task, time};
+use tokio::{
+    sync::{mpsc, watch},
+    task, time,
+};
 use tracing::{event, Level};
 
-use crate::prelude::*;
+use crate::{app::EventHandler, prelude::*};
 
 pub enum Event {
+    /// Sent periodically while widgets are animating.
     Tick,
+    /// Sent when something has changed outside the event loop, e.g. a request has finished.
+    Wake,
     Key(KeyEvent),
     Mouse(MouseEvent),
 }
@@ -25,10 +31,36 @@ pub enum Event {
 pub struct Tui {
     terminal: Terminal<CrosstermBackend<Stdout>>,
     rx: mpsc::UnboundedReceiver<Event>,
+    ticking: watch::Sender<bool>,
+    waker: Waker,
+}
+
+/// Lets tasks make the event loop render the view again, e.g. when a request has finished.
+///
+/// The default waker does nothing.
+#[derive(Clone, Debug, Default)]
+pub struct Waker(Option<mpsc::UnboundedSender<Event>>);
+
+impl Waker {
+    pub fn wake(&self) {
+        if let Some(tx) = &self.0 {
+            tx.send(Event::Wake).ok();
+        }
+    }
+}
+
+impl From<mpsc::UnboundedSender<Event>> for Waker {
+    fn from(tx: mpsc::UnboundedSender<Event>) -> Self {
+        Self(Some(tx))
+    }
 }
 
 impl Tui {
     pub fn new() -> Result<Self> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let waker = Waker::from(tx.clone());
+        let (ticking, ticking_rx) = watch::channel(true);
+        task::spawn(collect_event(tx, ticking_rx));
         Ok(Self {
             terminal: {
                 let backend = CrosstermBackend::new(stdout());
@@ -36,14 +68,17 @@ impl Tui {
                 terminal.clear()?;
                 terminal
             },
-            rx: {
-                let (tx, rx) = mpsc::unbounded_channel();
-                task::spawn(collect_event(tx));
-                rx
-            },
+            ticking,
+            waker,
+            rx,
         })
     }
 
+    /// Returns a waker of this event loop.
+    pub fn waker(&self) -> Waker {
+        self.waker.clone()
+    }
+
     #[inline]
     pub async fn event(&mut self) -> Option<Event> {
         self.rx.recv().await
@@ -52,6 +87,10 @@ impl Tui {
     #[inline]
     pub fn render(&mut self, view: &View) -> Result<()> {
         self.terminal.draw(|f| f.render_widget(view, f.size()))?;
+        // Nothing changes on the screen without events while no page is animating
+        let animating = view.is_animating();
+        self.ticking
+            .send_if_modified(|ticking| std::mem::replace(ticking, animating) != animating);
         Ok(())
     }
 }
@@ -66,8 +105,9 @@ impl From<Event> for tui_textarea::Input {
     }
 }
 
-async fn collect_event(tx: mpsc::UnboundedSender<Event>) {
+async fn collect_event(tx: mpsc::UnboundedSender<Event>, mut ticking: watch::Receiver<bool>) {
     let mut interval = time::interval(Duration::from_millis(250));
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
     let mut events = EventStream::new();
     event!(Level::TRACE, "start reading events");
     tx.send(Event::Tick).ok();
@@ -76,9 +116,15 @@ async fn collect_event(tx: mpsc::UnboundedSender<Event>) {
             _ = tx.closed() => {
                 break;
             }
-            _ = interval.tick() => {
+            _ = interval.tick(), if *ticking.borrow() => {
                 Some(Event::Tick)
             }
+            changed = ticking.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                None
+            }
             Some(Ok(event)) = events.next().fuse() => {
                 match event {
                     tui_event::Event::Key(key) => {
@@ -91,6 +137,7 @@ async fn collect_event(tx: mpsc::UnboundedSender<Event>) {
                     tui_event::Event::Mouse(mouse) => {
                         Some(Event::Mouse(mouse))
                     }
+                    tui_event::Event::Resize(..) => Some(Event::Wake),
                     _ => None,
                 }
             }
@@ -135,3 +182,30 @@ pub fn exit() -> Result<()> {
     event!(Level::TRACE, "finish rendering");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atp::{Response, StreamResponse};
+
+    #[tokio::test]
+    async fn wake_on_response() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let waker = Waker::from(tx);
+
+        let _res = Response::new(&waker, async {});
+        let event = time::timeout(Duration::from_secs(1), rx.recv()).await;
+        assert!(matches!(event, Ok(Some(Event::Wake))));
+
+        let res = StreamResponse::new(&waker, |tx| async move {
+            for i in 0..2 {
+                tx.send(i).await.ok();
+            }
+        });
+        for _ in 0..2 {
+            let event = time::timeout(Duration::from_secs(1), rx.recv()).await;
+            assert!(matches!(event, Ok(Some(Event::Wake))));
+            res.take_data();
+        }
+    }
+}
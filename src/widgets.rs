@@ -3,12 +3,17 @@ pub mod molecules;
 pub mod organisms;
 pub mod pages;
 mod post;
+mod reaction;
 mod rect_ext;
 mod store;
 pub mod templates;
 mod view;
 
-pub use post::{Posts, PostsState};
+pub use post::{
+    content as post_content, scroll_to, EmbedRecord, Post, Posts, PostsState, BORDER,
+    SELECTED_BORDER,
+};
+pub use reaction::Reaction;
 pub use rect_ext::RectExt;
 use store::{Store, Storeable};
 pub use view::{View, ViewID};
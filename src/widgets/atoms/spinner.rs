@@ -1,10 +1,22 @@
+use std::time::Duration;
+
 use ratatui::{prelude::*, widgets::*};
 
-pub struct Spinner;
+pub struct Spinner {
+    rate_limit_wait: Option<Duration>,
+}
 
 impl Spinner {
     pub fn new() -> Self {
-        Self
+        Self {
+            rate_limit_wait: None,
+        }
+    }
+
+    /// Tells the user that the request is waiting for the rate limit instead of spinning.
+    pub fn rate_limit_wait(mut self, wait: Option<Duration>) -> Self {
+        self.rate_limit_wait = wait;
+        self
     }
 }
 
@@ -14,6 +26,23 @@ impl WidgetRef for Spinner {
             return;
         }
 
+        if let Some(wait) = self.rate_limit_wait {
+            let [_, area, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ])
+            .areas(area);
+            Line::from(format!(
+                "Waiting for the rate limit ({}s)",
+                wait.as_secs_f32().ceil()
+            ))
+            .yellow()
+            .centered()
+            .render(area, buf);
+            return;
+        }
+
         let dot = "•";
         const N: usize = 5;
 
@@ -52,3 +81,19 @@ impl WidgetRef for Spinner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_rate_limit_wait() {
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = Buffer::empty(area);
+        Spinner::new()
+            .rate_limit_wait(Some(Duration::from_millis(2500)))
+            .render_ref(area, &mut buf);
+        let line: String = buf.content[40..80].iter().map(|c| c.symbol()).collect();
+        assert_eq!("    Waiting for the rate limit (3s)     ", line);
+    }
+}
@@ -0,0 +1,64 @@
+use ratatui::{prelude::*, widgets::WidgetRef};
+
+use super::Dialog;
+
+/// A box asking whether to go ahead with an action, at the center of an area.
+pub struct Confirm {
+    message: String,
+}
+
+impl Confirm {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl WidgetRef for Confirm {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(Dialog::HEIGHT),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        Dialog::new(
+            " Confirm ",
+            Color::Yellow,
+            &self.message,
+            " y: yes    n: no",
+        )
+        .render_ref(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_at_center() {
+        let area = Rect::new(0, 0, 30, 8);
+        let mut buf = Buffer::empty(area);
+        Confirm::new("Delete this post?").render_ref(area, &mut buf);
+        let lines: Vec<String> = buf
+            .content
+            .chunks(area.width as usize)
+            .map(|cells| cells.iter().map(|c| c.symbol()).collect())
+            .collect();
+        assert_eq!(
+            vec![
+                "                              ",
+                "╭ Confirm ───────────────────╮",
+                "│ Delete this post?          │",
+                "│                            │",
+                "│                            │",
+                "│  y: yes    n: no           │",
+                "╰────────────────────────────╯",
+                "                              ",
+            ],
+            lines
+        );
+    }
+}
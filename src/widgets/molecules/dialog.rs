@@ -0,0 +1,80 @@
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    prelude::*,
+    widgets::{
+        atoms::{BlockExt, Text},
+        Store, Storeable,
+    },
+};
+
+/// A box with a message and the keys to answer it, drawn over an area.
+pub struct Dialog<'a> {
+    title: &'a str,
+    color: Color,
+    message: &'a str,
+    help: &'a str,
+}
+
+impl<'a> Dialog<'a> {
+    pub const HEIGHT: u16 = 6;
+
+    pub fn new(title: &'a str, color: Color, message: &'a str, help: &'a str) -> Self {
+        Self {
+            title,
+            color,
+            message,
+            help,
+        }
+    }
+}
+
+impl WidgetRef for Dialog<'_> {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+
+        let mut store = Store::new();
+        let message = Text::from(self.message.to_string());
+        let help = Text::from(self.help.to_string().dim());
+        Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(Style::new().fg(self.color))
+            .title(self.title.fg(self.color).bold())
+            .padding(Padding::horizontal(1))
+            .wrap(move |inner, s| {
+                message.store(inner.height(inner.height.saturating_sub(1)), s);
+                help.store(inner.y(inner.bottom().saturating_sub(1)).height(1), s);
+            })
+            .store(area, &mut store);
+        store.render_ref(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let area = Rect::new(0, 0, 30, Dialog::HEIGHT);
+        let mut buf = Buffer::empty(area);
+        Dialog::new(" Error ", Color::Red, "network is unreachable", " r: retry")
+            .render_ref(area, &mut buf);
+        let lines: Vec<String> = buf
+            .content
+            .chunks(area.width as usize)
+            .map(|cells| cells.iter().map(|c| c.symbol()).collect())
+            .collect();
+        assert_eq!(
+            vec![
+                "╭ Error ─────────────────────╮",
+                "│ network is unreachable     │",
+                "│                            │",
+                "│                            │",
+                "│  r: retry                  │",
+                "╰────────────────────────────╯",
+            ],
+            lines
+        );
+    }
+}
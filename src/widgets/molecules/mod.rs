@@ -1,3 +1,9 @@
+mod confirm;
+mod dialog;
 mod tab;
+mod toast;
 
+pub use confirm::Confirm;
+pub use dialog::Dialog;
 pub use tab::Tab;
+pub use toast::Toast;
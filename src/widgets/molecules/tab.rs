@@ -14,6 +14,8 @@ pub struct Tab {
     text: String,
     active: bool,
     selected: bool,
+    /// The number of new items, shown next to the text unless it is 0
+    badge: u64,
 }
 
 impl Tab {
@@ -22,6 +24,7 @@ impl Tab {
             text: text.into(),
             active: true,
             selected: false,
+            badge: 0,
         }
     }
 
@@ -33,6 +36,10 @@ impl Tab {
         self.selected = value;
         self
     }
+    pub fn badge(mut self, value: u64) -> Self {
+        self.badge = value;
+        self
+    }
 }
 
 impl Storeable<'_> for Tab {
@@ -46,15 +53,21 @@ impl Storeable<'_> for Tab {
         };
         Block::new()
             .padding(Padding::vertical(1))
-            .wrap_child(Text::from_iter([
-                if self.selected {
-                    ratatui::symbols::line::THICK_VERTICAL.blue()
-                } else {
-                    " ".into()
-                },
-                " ".into(),
-                self.text.clone().set_style(style),
-            ]))
+            .wrap_child(Text::from_iter({
+                let mut spans = vec![
+                    if self.selected {
+                        ratatui::symbols::line::THICK_VERTICAL.blue()
+                    } else {
+                        " ".into()
+                    },
+                    " ".into(),
+                    self.text.clone().set_style(style),
+                ];
+                if self.badge > 0 {
+                    spans.extend([" ".into(), format!(" {} ", self.badge).white().on_red()]);
+                }
+                spans
+            }))
             .fit_vertical()
             .store(area, store);
     }
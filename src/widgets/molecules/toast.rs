@@ -0,0 +1,60 @@
+use ratatui::{prelude::*, widgets::WidgetRef};
+
+use super::Dialog;
+
+/// A box showing an error message at the bottom of an area.
+pub struct Toast {
+    message: String,
+}
+
+impl Toast {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl WidgetRef for Toast {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(Dialog::HEIGHT)]).areas(area);
+        Dialog::new(
+            " Error ",
+            Color::Red,
+            &self.message,
+            " r: retry    x: dismiss",
+        )
+        .render_ref(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_at_bottom() {
+        let area = Rect::new(0, 0, 30, 8);
+        let mut buf = Buffer::empty(area);
+        Toast::new("network is unreachable").render_ref(area, &mut buf);
+        let lines: Vec<String> = buf
+            .content
+            .chunks(area.width as usize)
+            .map(|cells| cells.iter().map(|c| c.symbol()).collect())
+            .collect();
+        assert_eq!(
+            vec![
+                "                              ",
+                "                              ",
+                "╭ Error ─────────────────────╮",
+                "│ network is unreachable     │",
+                "│                            │",
+                "│                            │",
+                "│  r: retry    x: dismiss    │",
+                "╰────────────────────────────╯",
+            ],
+            lines
+        );
+    }
+}
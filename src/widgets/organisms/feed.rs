@@ -0,0 +1,258 @@
+use std::cell::RefCell;
+
+use atrium_api::app::bsky::feed::defs::FeedViewPost;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    atp::{FeedSource, Response, StreamResponse},
+    prelude::*,
+    widgets::{atoms::Spinner, molecules::Confirm, Post, Posts, PostsState, ViewID},
+};
+
+/// Posts of a feed fetched page by page as the user scrolls,
+/// with the keys to read, react to, and delete them.
+#[derive(Debug)]
+pub struct Feed {
+    source: FeedSource,
+    posts: Posts,
+    posts_state: RefCell<PostsState>,
+    /// Pages of the feed from the cursor
+    response: StreamResponse<crate::atp::FeedResult>,
+    /// Used to get old posts
+    post_cursor: Option<String>,
+    /// Stops fetching posts until the user retries
+    failed: bool,
+    /// There are no older posts to fetch
+    reached_end: bool,
+    rate_limit_wait: Option<std::time::Duration>,
+    /// The URI of the post to delete once the user confirms it
+    confirm_delete: Option<String>,
+    /// The URI of the post being deleted
+    deleting: Option<String>,
+    delete_res: Response<crate::atp::DeletePostResult>,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Self::new(FeedSource::Timeline)
+    }
+}
+
+impl Feed {
+    pub fn new(source: FeedSource) -> Self {
+        Self {
+            source,
+            posts: Posts::default(),
+            posts_state: RefCell::default(),
+            response: StreamResponse::default(),
+            post_cursor: None,
+            failed: false,
+            reached_end: false,
+            rate_limit_wait: None,
+            confirm_delete: None,
+            deleting: None,
+            delete_res: Response::default(),
+        }
+    }
+
+    /// Whether the keys go to the confirmation dialog.
+    pub fn is_confirming(&self) -> bool {
+        self.confirm_delete.is_some()
+    }
+
+    pub fn recv_page(&mut self, page: crate::atp::Page<FeedViewPost>) {
+        self.reached_end = page.cursor.is_none();
+        self.post_cursor = page.cursor;
+        for post in page.items {
+            self.posts.add_post(post, false);
+        }
+    }
+
+    /// Asks to delete the selected post if the account has written it.
+    fn confirm_delete(&mut self, app: &App) {
+        let Some(post) = self.posts.selected() else {
+            return;
+        };
+        if self.deleting.is_none() && Some(&post.author.did) == app.atp().account().did.as_ref() {
+            self.confirm_delete = Some(post.uri.clone());
+        }
+    }
+
+    fn delete(&mut self, app: &App) {
+        let Some(uri) = self.confirm_delete.take() else {
+            return;
+        };
+        self.posts.update_post(&uri, |post| post.set_deleting(true));
+        self.delete_res = app.atp().delete_post(uri.clone());
+        self.deleting = Some(uri);
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.posts.scroll = self.posts.scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.posts.scroll += 1;
+    }
+}
+
+impl WidgetRef for Feed {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        // posts
+        let mut posts_state = self.posts_state.borrow_mut();
+        self.posts.render_ref(area, buf, &mut posts_state);
+
+        // spinner
+        if let Some(blank_height) = posts_state
+            .blank_height
+            .filter(|_| self.response.is_loading())
+        {
+            let blank_area = Rect {
+                height: blank_height,
+                y: area.bottom() - blank_height,
+                ..area
+            };
+            let [_, spinner_area] = Layout::vertical([
+                Constraint::Length(14_u16.saturating_sub(blank_area.height)),
+                Constraint::Fill(1),
+            ])
+            .areas(blank_area);
+            Spinner::new()
+                .rate_limit_wait(self.rate_limit_wait)
+                .render_ref(spinner_area, buf);
+        }
+
+        if self.confirm_delete.is_some() {
+            Confirm::new("Delete this post?").render_ref(area, buf);
+        }
+    }
+}
+
+impl crate::app::EventHandler for Feed {
+    fn on_render(&mut self, app: &mut App) {
+        self.rate_limit_wait = app.rate_limit().waiting_for();
+        self.posts.apply_reactions(app);
+        if let Some(result) = self.delete_res.take_data() {
+            let uri = self.deleting.take().unwrap_or_default();
+            match result {
+                Ok(()) => {
+                    self.posts.remove_post(&uri);
+                }
+                Err(err) => {
+                    self.posts
+                        .update_post(&uri, |post| post.set_deleting(false));
+                    app.report_error(err.context("failed to delete the post"));
+                }
+            }
+        }
+        // Take the next page only when there is space to show it
+        if self.posts_state.borrow().blank_height.is_none() || self.failed || self.reached_end {
+            return;
+        }
+        if self.response.is_empty() {
+            self.response = app
+                .atp()
+                .get_feed(self.source.clone(), self.post_cursor.clone());
+        }
+
+        match self.response.take_data() {
+            Some(Ok(page)) => self.recv_page(page),
+            Some(Err(err)) => {
+                self.failed = true;
+                let context = match self.source {
+                    FeedSource::Timeline => "failed to get the timeline",
+                    FeedSource::Author(_) => "failed to get the posts",
+                    FeedSource::Search { .. } => "failed to search the posts",
+                };
+                app.report_error(err.context(context));
+            }
+            None => {}
+        }
+    }
+
+    fn on_retry(&mut self, _app: &mut App) {
+        self.failed = false;
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        if self.confirm_delete.is_some() {
+            match ev.code {
+                KeyCode::Char('y') => self.delete(app),
+                KeyCode::Char('n') | KeyCode::Esc => self.confirm_delete = None,
+                _ => {}
+            }
+            return;
+        }
+        if ev.code == KeyCode::Char('k') {
+            self.scroll_up();
+        }
+        if ev.code == KeyCode::Char('j') {
+            self.scroll_down();
+        }
+        if ev.code == KeyCode::Up {
+            self.posts.select_prev(self.posts_state.get_mut());
+        }
+        if ev.code == KeyCode::Down {
+            self.posts.select_next(self.posts_state.get_mut());
+        }
+        if ev.code == KeyCode::Char('l') {
+            self.posts.toggle_like(app.atp());
+        }
+        if ev.code == KeyCode::Char('t') {
+            self.posts.toggle_repost(app.atp());
+        }
+        if ev.code == KeyCode::Enter {
+            if let Some(post) = self.posts.selected() {
+                app.set_view_id(ViewID::Thread {
+                    uri: post.uri.clone(),
+                });
+            }
+        }
+        if ev.code == KeyCode::Char('p') {
+            if let Some(post) = self.posts.selected() {
+                app.set_view_id(ViewID::Profile {
+                    actor: post.author.did.as_str().to_string(),
+                });
+            }
+        }
+        // The account in "Reposted by"
+        if ev.code == KeyCode::Char('b') {
+            if let Some(did) = self.posts.selected_post().and_then(Post::reposted_by) {
+                app.set_view_id(ViewID::Profile {
+                    actor: did.to_string(),
+                });
+            }
+        }
+        // The author of the quoted post
+        if ev.code == KeyCode::Char('o') {
+            if let Some(did) = self.posts.selected_post().and_then(Post::quoted_author) {
+                app.set_view_id(ViewID::Profile {
+                    actor: did.to_string(),
+                });
+            }
+        }
+        if ev.code == KeyCode::Char('d') {
+            self.confirm_delete(app);
+        }
+        if ev.code == KeyCode::Char('r') {
+            if let Some(post) = self.posts.selected() {
+                app.set_view_id(ViewID::Compose {
+                    reply_to: Some(Box::new(post.clone())),
+                    quote: None,
+                });
+            }
+        }
+        if ev.code == KeyCode::Char('q') {
+            if let Some(post) = self.posts.selected() {
+                app.set_view_id(ViewID::Compose {
+                    reply_to: None,
+                    quote: Some(Box::new(post.clone())),
+                });
+            }
+        }
+    }
+
+    fn is_animating(&self) -> bool {
+        self.response.is_loading()
+    }
+}
@@ -1,3 +1,7 @@
+mod feed;
+mod profile_list;
 mod tabbar;
 
+pub use feed::Feed;
+pub use profile_list::ProfileList;
 pub use tabbar::TabBar;
@@ -0,0 +1,173 @@
+use std::cell::Cell;
+
+use atrium_api::app::bsky::actor::defs::ProfileView;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    atp::{ActorList, ActorSource, StreamResponse},
+    prelude::*,
+    widgets::{
+        atoms::{BlockExt, Spinner, Text},
+        scroll_to, Store, Storeable, ViewID, BORDER, SELECTED_BORDER,
+    },
+};
+
+/// The height of a row: the name, the first line of the bio, and the border.
+const ROW_HEIGHT: u16 = 3;
+
+/// Accounts fetched page by page as the user scrolls, each opening its profile.
+#[derive(Debug, Default)]
+pub struct ProfileList {
+    source: Option<ActorSource>,
+    rows: Vec<ProfileView>,
+    selected: usize,
+    scroll: u16,
+    /// Pages of the list from the cursor
+    response: StreamResponse<crate::atp::ActorsResult>,
+    cursor: Option<String>,
+    /// Stops fetching rows until the user retries
+    failed: bool,
+    /// There are no more rows to fetch
+    reached_end: bool,
+    /// The height of the rows in the last render, to fetch more rows when they do not fill it.
+    height: Cell<u16>,
+}
+
+impl ProfileList {
+    pub fn new(source: ActorSource) -> Self {
+        Self {
+            source: Some(source),
+            ..Default::default()
+        }
+    }
+
+    pub fn source(&self) -> Option<&ActorSource> {
+        self.source.as_ref()
+    }
+
+    /// Whether the rows do not fill the screen below the scroll position.
+    fn has_space(&self) -> bool {
+        ROW_HEIGHT * self.rows.len() as u16 <= self.scroll + self.height.get()
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+        let top = ROW_HEIGHT * self.selected as u16;
+        self.scroll = scroll_to(self.scroll, &(top..top + ROW_HEIGHT), self.height.get());
+    }
+}
+
+impl WidgetRef for ProfileList {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        self.height.set(area.height);
+        let mut store = Store::new().scroll_v(self.scroll as i32);
+        for (i, row) in self.rows.iter().enumerate() {
+            let block = if i == self.selected {
+                Block::new()
+                    .borders(Borders::LEFT | Borders::BOTTOM)
+                    .border_set(SELECTED_BORDER)
+                    .border_style(Style::new().blue())
+            } else {
+                Block::new()
+                    .borders(Borders::LEFT | Borders::BOTTOM)
+                    .border_set(BORDER)
+                    .border_style(Style::new().blue().dim())
+            };
+            block
+                .padding(Padding::left(1))
+                .wrap(move |inner, s| store_row(row, inner, s))
+                .store(
+                    store.bottom_space(area.height(u16::MAX)).height(ROW_HEIGHT),
+                    &mut store,
+                );
+        }
+        store.render_ref(area, buf);
+
+        if self.has_space() && self.response.is_loading() {
+            let [_, spinner_area] = Layout::vertical([
+                Constraint::Length(
+                    (ROW_HEIGHT * self.rows.len() as u16).saturating_sub(self.scroll),
+                ),
+                Constraint::Fill(1),
+            ])
+            .areas(area);
+            Spinner::new().render_ref(spinner_area, buf);
+        }
+    }
+}
+
+fn store_row<'a>(row: &'a ProfileView, area: Rect, store: &mut Store<'a>) {
+    Text::from_iter({
+        let mut spans = Vec::new();
+        if let Some(name) = row.display_name.as_ref().filter(|name| !name.is_empty()) {
+            spans.extend([name.clone().bold(), "  ".into()]);
+        }
+        spans.push(format!("@{}", row.handle.as_str()).dim());
+        spans
+    })
+    .store(store.bottom_space(area).height(1), store);
+    if let Some(bio) = row.description.as_ref().and_then(|bio| bio.lines().next()) {
+        Text::from(bio.to_string().italic()).store(store.bottom_space(area).height(1), store);
+    }
+}
+
+impl crate::app::EventHandler for ProfileList {
+    fn on_render(&mut self, app: &mut App) {
+        let Some(source) = &self.source else {
+            return;
+        };
+        // Take the next page only when there is space to show it
+        if !self.has_space() || self.failed || self.reached_end {
+            return;
+        }
+        if self.response.is_empty() {
+            self.response = app.atp().get_actors(source.clone(), self.cursor.clone());
+        }
+        match self.response.take_data() {
+            Some(Ok(page)) => {
+                self.reached_end = page.cursor.is_none();
+                self.cursor = page.cursor;
+                self.rows.extend(page.items);
+            }
+            Some(Err(err)) => {
+                self.failed = true;
+                let context = match source {
+                    ActorSource::Related {
+                        list: ActorList::Followers,
+                        ..
+                    } => "failed to get the followers",
+                    ActorSource::Related {
+                        list: ActorList::Follows,
+                        ..
+                    } => "failed to get the follows",
+                    ActorSource::Search(_) => "failed to search the accounts",
+                };
+                app.report_error(err.context(context));
+            }
+            None => {}
+        }
+    }
+
+    fn on_retry(&mut self, _app: &mut App) {
+        self.failed = false;
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        match ev.code {
+            KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select(self.selected + 1),
+            KeyCode::Enter => {
+                if let Some(row) = self.rows.get(self.selected) {
+                    app.set_view_id(ViewID::Profile {
+                        actor: row.did.as_str().to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_animating(&self) -> bool {
+        self.response.is_loading()
+    }
+}
@@ -0,0 +1,208 @@
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    prelude::*,
+    widgets::{
+        atoms::{BlockExt, Text},
+        Store, Storeable,
+    },
+};
+
+/// Lists the signed-in accounts to switch between them.
+#[derive(Debug, Default)]
+pub struct Accounts {
+    accounts: Vec<Account>,
+    active: usize,
+    selected: usize,
+}
+
+#[derive(Debug)]
+struct Account {
+    handle: Option<String>,
+    did: Option<String>,
+    endpoint: String,
+}
+
+impl From<crate::atp::Account> for Account {
+    fn from(value: crate::atp::Account) -> Self {
+        Self {
+            handle: value.handle.map(|h| format!("@{}", h.as_str())),
+            did: value.did.map(String::from),
+            endpoint: value.endpoint,
+        }
+    }
+}
+
+impl Accounts {
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.accounts.len().saturating_sub(1));
+    }
+}
+
+impl WidgetRef for Accounts {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+
+        let mut store = Store::new();
+        for (i, account) in self.accounts.iter().enumerate() {
+            let mut name = vec![
+                if i == self.selected {
+                    ratatui::symbols::line::THICK_VERTICAL.blue()
+                } else {
+                    " ".into()
+                },
+                " ".into(),
+                account
+                    .handle
+                    .clone()
+                    // The handle is unknown until an encrypted session is resumed
+                    .or_else(|| account.did.clone())
+                    .unwrap_or_else(|| String::from("(not signed in)"))
+                    .bold(),
+            ];
+            if i == self.active {
+                name.extend(["  ".into(), "active".green().italic()]);
+            }
+            let details = format!(
+                "   {}  {}",
+                account.did.as_deref().unwrap_or_default(),
+                account.endpoint
+            );
+            Block::new()
+                .borders(Borders::BOTTOM)
+                .border_style(Style::new().blue().dim())
+                .wrap(move |area, s| {
+                    Text::from_iter(name).store(s.bottom_space(area).height(1), s);
+                    Text::from(details.dim()).store(s.bottom_space(area).height(1), s);
+                })
+                .fit_vertical()
+                .store(store.bottom_space(list_area), &mut store);
+        }
+        store.render_ref(list_area, buf);
+
+        Line::from(" Enter: switch    n: add account".dim()).render(help_area, buf);
+    }
+}
+
+impl crate::app::EventHandler for Accounts {
+    fn on_render(&mut self, app: &mut App) {
+        self.accounts = app
+            .accounts()
+            .iter()
+            .map(|atp| atp.account().into())
+            .collect();
+        self.active = app.active_account();
+        self.selected = self.selected.min(self.accounts.len().saturating_sub(1));
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        match ev.code {
+            KeyCode::Esc => app.exit(),
+            KeyCode::Char('k') => self.select_prev(),
+            KeyCode::Char('j') => self.select_next(),
+            KeyCode::Enter => app.switch_account(self.selected),
+            KeyCode::Char('n') => app.add_account(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{testing::Harness, EventHandler},
+        atp::fixture::FixtureClient,
+    };
+
+    fn handles(h: &Harness) -> Vec<String> {
+        h.app
+            .accounts()
+            .iter()
+            .filter_map(|atp| Some(atp.account().handle?.as_str().to_string()))
+            .collect()
+    }
+
+    async fn sign_in_as_bob(h: &mut Harness) {
+        h.key(KeyCode::Tab);
+        assert!(h.view.focus_in_textarea());
+        h.type_str("bob.test");
+        h.key(KeyCode::Tab);
+        h.type_str("abcd-efgh-ijkl-mnop");
+        h.key(KeyCode::Enter);
+        h.wait_until(|h| h.screen().contains("3. @bob.test")).await;
+    }
+
+    #[tokio::test]
+    async fn add_account() {
+        let mut h = Harness::signed_in(FixtureClient::default().sign_in_as("bob")).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('3'));
+        h.key(KeyCode::Char('n'));
+        sign_in_as_bob(&mut h).await;
+
+        assert_eq!(vec!["alice.test", "bob.test"], handles(&h));
+        assert_eq!(1, h.app.active_account());
+    }
+
+    #[tokio::test]
+    async fn switch_account() {
+        let mut h = Harness::signed_in(FixtureClient::default().sign_in_as("bob")).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('3'));
+        h.key(KeyCode::Char('n'));
+        sign_in_as_bob(&mut h).await;
+
+        h.key(KeyCode::Char('3'));
+        h.key(KeyCode::Char('k'));
+        h.key(KeyCode::Enter);
+        h.wait_until(|h| h.screen().contains("3. @alice.test"))
+            .await;
+        assert_eq!(0, h.app.active_account());
+
+        h.key(KeyCode::Char('3'));
+        h.key(KeyCode::Char('j'));
+        h.key(KeyCode::Enter);
+        h.wait_until(|h| h.screen().contains("3. @bob.test")).await;
+        assert_eq!(1, h.app.active_account());
+        assert_eq!(None, h.app.error());
+        assert_eq!(vec!["alice.test", "bob.test"], handles(&h));
+    }
+
+    #[tokio::test]
+    async fn sign_in_again() {
+        let mut h = Harness::signed_in(FixtureClient::default().sign_in_as("bob")).await;
+        h.wait_for_timeline().await;
+        // The login page of the account that has signed in
+        h.key(KeyCode::Char('1'));
+        sign_in_as_bob(&mut h).await;
+
+        assert_eq!(vec!["alice.test", "bob.test"], handles(&h));
+        assert_eq!(1, h.app.active_account());
+    }
+
+    #[tokio::test]
+    async fn sign_in_again_to_same_account() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('1'));
+        h.key(KeyCode::Tab);
+        h.type_str("alice.test");
+        h.key(KeyCode::Enter);
+        h.wait_for_timeline().await;
+
+        assert_eq!(vec!["alice.test"], handles(&h));
+        assert_eq!(0, h.app.active_account());
+    }
+}
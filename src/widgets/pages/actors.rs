@@ -0,0 +1,123 @@
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    atp::{ActorList, ActorSource},
+    prelude::*,
+    widgets::{organisms::ProfileList, ViewID},
+};
+
+/// Lists the followers or the follows of an actor.
+#[derive(Debug, Default)]
+pub struct Actors {
+    list: ProfileList,
+}
+
+impl WidgetRef for Actors {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [title_area, rows_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(area);
+
+        if let Some(ActorSource::Related { actor, list }) = self.list.source() {
+            let title = match list {
+                ActorList::Followers => " Followers",
+                ActorList::Follows => " Following",
+            };
+            Line::from_iter([title.bold(), format!(" · {actor}").dim()]).render(title_area, buf);
+        }
+        self.list.render_ref(rows_area, buf);
+    }
+}
+
+impl crate::app::EventHandler for Actors {
+    fn on_render(&mut self, app: &mut App) {
+        if let ViewID::Actors { actor, list } = app.view_id() {
+            let source = ActorSource::Related {
+                actor: actor.clone(),
+                list: *list,
+            };
+            if self.list.source() != Some(&source) {
+                self.list = ProfileList::new(source);
+            }
+        }
+        self.list.on_render(app);
+    }
+
+    fn on_retry(&mut self, app: &mut App) {
+        self.list.on_retry(app);
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        if ev.code == KeyCode::Esc {
+            if let Some(ActorSource::Related { actor, .. }) = self.list.source() {
+                app.set_view_id(ViewID::Profile {
+                    actor: actor.clone(),
+                });
+            }
+            return;
+        }
+        self.list.on_key(ev, app);
+    }
+
+    fn is_animating(&self) -> bool {
+        self.list.is_animating()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::testing::Harness, atp::fixture::FixtureClient};
+
+    async fn open_profile(h: &mut Harness) {
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+        h.wait_until(|h| h.screen().contains("Only on my profile."))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn followers() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        open_profile(&mut h).await;
+        h.key(KeyCode::Char('F'));
+
+        h.wait_until(|h| h.screen().contains("@carol.test")).await;
+        let screen = h.screen();
+        assert!(screen.contains("Followers · did:plc:bob"));
+        assert!(screen.contains("Alice  @alice.test"));
+        assert!(screen.contains("Reads the fixtures."));
+        assert!(!screen.contains("Sometimes writes them."));
+
+        h.key(KeyCode::Down);
+        h.key(KeyCode::Enter);
+        h.send(TuiEvent::Tick);
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("did:plc:carol")
+            },
+            h.app.view_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn follows() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        open_profile(&mut h).await;
+        h.key(KeyCode::Char('G'));
+
+        h.wait_until(|h| h.screen().contains("Tests everything twice."))
+            .await;
+        assert!(h.screen().contains("Following · did:plc:bob"));
+
+        // Back to the same profile without fetching it again
+        h.key(KeyCode::Esc);
+        h.send(TuiEvent::Tick);
+        assert!(h.screen().contains("Only on my profile."));
+    }
+}
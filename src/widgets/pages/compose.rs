@@ -0,0 +1,665 @@
+use std::path::PathBuf;
+
+use atrium_api::{
+    app::bsky::{self, feed::defs::PostView},
+    com::atproto::repo::strong_ref,
+    records,
+    types::{string::Datetime, Union},
+};
+use crossterm::event::KeyCode;
+use ratatui::{prelude::*, widgets::*};
+#[cfg(test)]
+use rstest::rstest;
+use tui_textarea::{Input, Key};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    app::App,
+    atp::Response,
+    widgets::{
+        atoms::{BlockExt, Spinner, TextArea},
+        EmbedRecord, Post, Store, Storeable, ViewID,
+    },
+};
+
+/// The maximum length of a post in graphemes.
+const MAX_GRAPHEMES: usize = 300;
+/// The maximum number of images in a post.
+const MAX_IMAGES: usize = 4;
+
+/// Writes and publishes a new post, a reply or a quote.
+#[derive(Debug)]
+pub struct Compose {
+    textarea: TextArea<'static>,
+    focus: bool,
+    post_res: Response<crate::atp::CreatePostResult>,
+    posted: bool,
+    reply_to: Option<Box<PostView>>,
+    /// `reply_to` shown above the editor.
+    parent: Option<Post>,
+    quote: Option<Box<PostView>>,
+    /// `quote` shown below the editor.
+    quote_preview: Option<EmbedRecord>,
+    /// Images uploaded as soon as they are attached.
+    images: Vec<Attachment>,
+    /// The prompt for the path of an image to attach
+    attach: Option<TextArea<'static>>,
+}
+
+#[derive(Debug)]
+struct Attachment {
+    /// The file name of the image
+    name: String,
+    upload_res: Response<crate::atp::UploadImageResult>,
+    /// Set once the image is uploaded
+    image: Option<bsky::embed::images::Image>,
+}
+
+impl Default for Compose {
+    fn default() -> Self {
+        let mut compose = Self {
+            textarea: TextArea::new(" New post ", false),
+            focus: false,
+            post_res: Response::empty(),
+            posted: false,
+            reply_to: None,
+            parent: None,
+            quote: None,
+            quote_preview: None,
+            images: Vec::new(),
+            attach: None,
+        };
+        compose.set_focus();
+        compose
+    }
+}
+
+impl Compose {
+    /// Starts over to write a post replying to `reply_to` and quoting `quote`.
+    fn reset(&mut self, reply_to: Option<Box<PostView>>, quote: Option<Box<PostView>>) {
+        *self = Self {
+            parent: reply_to.as_deref().cloned().map(Post::from),
+            reply_to,
+            quote_preview: quote.as_deref().cloned().map(EmbedRecord::from),
+            quote,
+            ..Default::default()
+        };
+        self.clear();
+    }
+
+    fn clear(&mut self) {
+        let title = if self.reply_to.is_some() {
+            " Reply "
+        } else {
+            " New post "
+        };
+        self.textarea = TextArea::new(title, false);
+        self.set_focus();
+    }
+
+    pub fn text(&self) -> String {
+        self.textarea.lines().join("\n")
+    }
+
+    fn can_post(&self) -> bool {
+        let text = self.text();
+        // The text of a quote or of images may be empty
+        (!text.trim().is_empty() || self.quote.is_some() || !self.images.is_empty())
+            && grapheme_count(&text) <= MAX_GRAPHEMES
+            && self.images.iter().all(|image| image.image.is_some())
+            && !self.post_res.is_loading()
+    }
+
+    fn open_attach(&mut self) {
+        if self.images.len() >= MAX_IMAGES || self.post_res.is_loading() {
+            return;
+        }
+        let mut textarea = TextArea::new(" Image file ", false);
+        textarea.set_focus();
+        self.attach = Some(textarea);
+    }
+
+    /// Starts uploading the image at the path in the prompt.
+    fn attach(&mut self, app: &App) {
+        let Some(textarea) = self.attach.take() else {
+            return;
+        };
+        let path = PathBuf::from(textarea.lines()[0].trim());
+        if path.as_os_str().is_empty() {
+            return;
+        }
+        self.images.push(Attachment {
+            name: path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            ),
+            upload_res: app.atp().upload_image(path),
+            image: None,
+        });
+        self.posted = false;
+        self.set_focus();
+    }
+
+    fn post(&mut self, app: &mut App) {
+        if !self.can_post() {
+            return;
+        }
+        self.post_res = app.atp().create_post(bsky::feed::post::Record {
+            created_at: Datetime::now(),
+            embed: embed(
+                self.quote.as_deref(),
+                self.images.iter().filter_map(|image| image.image.clone()),
+            ),
+            entities: None,
+            facets: None,
+            labels: None,
+            langs: None,
+            reply: self.reply_to.as_deref().map(reply_ref),
+            tags: None,
+            text: self.text(),
+        });
+        self.lose_focus();
+    }
+
+    fn set_focus(&mut self) {
+        self.focus = true;
+        self.textarea.set_focus();
+    }
+
+    fn lose_focus(&mut self) {
+        self.focus = false;
+        self.textarea.lose_focus();
+    }
+}
+
+impl WidgetRef for Compose {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [_, parent, editor, images, quote, status, spinner, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Max(if self.parent.is_some() { 12 } else { 0 }),
+            Constraint::Length(10),
+            Constraint::Length(self.images.len() as u16),
+            Constraint::Max(if self.quote_preview.is_some() { 12 } else { 0 }),
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        if let Some(post) = &self.parent {
+            let mut store = Store::new();
+            Block::bordered()
+                .border_type(BorderType::Rounded)
+                .border_style(Style::new().dim())
+                .title(" Replying to ")
+                .padding(Padding::horizontal(1))
+                .wrap_child(post)
+                .fit_vertical()
+                .store(parent, &mut store);
+            store.render_ref(parent, buf);
+        }
+        self.textarea.widget().render(editor, buf);
+        for (image, area) in self.images.iter().zip(images.rows()) {
+            let mut line = Line::from_iter([" \u{f03e} ".blue(), image.name.as_str().into()]);
+            if image.image.is_none() {
+                line.push_span("  uploading…".dim());
+            }
+            line.render(area, buf);
+        }
+        if let Some(area) = images.rows().last() {
+            Line::from("Backspace: remove ".dim())
+                .right_aligned()
+                .render(area, buf);
+        }
+        if let Some(embed) = &self.quote_preview {
+            let mut store = Store::new();
+            embed.store(quote, &mut store);
+            store.render_ref(quote, buf);
+        }
+
+        let count = grapheme_count(&self.text());
+        let counter = format!("{count}/{MAX_GRAPHEMES} ");
+        let counter = if count > MAX_GRAPHEMES {
+            counter.red().bold()
+        } else {
+            counter.dim()
+        };
+        Line::from(counter).right_aligned().render(status, buf);
+        if self.posted {
+            Line::from(" ✓ Posted.".green()).render(status, buf);
+        } else if count > MAX_GRAPHEMES {
+            Line::from(" The post is too long.".red()).render(status, buf);
+        }
+
+        if self.post_res.is_loading() {
+            Spinner::new().render_ref(spinner, buf);
+        }
+        Line::from(" Ctrl+S: post    a: attach an image    Esc: back".dim()).render(help, buf);
+
+        if let Some(textarea) = &self.attach {
+            let [_, prompt, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(3),
+                Constraint::Fill(1),
+            ])
+            .areas(area);
+            Clear.render(prompt, buf);
+            textarea.widget().render(prompt, buf);
+        }
+    }
+}
+
+impl crate::app::EventHandler for Compose {
+    fn on_render(&mut self, app: &mut App) {
+        if let ViewID::Compose { reply_to, quote } = app.view_id() {
+            if reply_to != &self.reply_to || quote != &self.quote {
+                self.reset(reply_to.clone(), quote.clone());
+            }
+        }
+        let mut failed = Vec::new();
+        self.images
+            .retain_mut(|image| match image.upload_res.take_data() {
+                Some(Ok(uploaded)) => {
+                    image.image = Some(uploaded);
+                    true
+                }
+                Some(Err(err)) => {
+                    failed.push(err);
+                    false
+                }
+                None => true,
+            });
+        for err in failed {
+            app.report_error(err.context("failed to attach the image"));
+        }
+        match self.post_res.take_data() {
+            Some(Ok(_)) => {
+                self.posted = true;
+                self.images.clear();
+                // Quoting the post again would publish the same quote
+                if self.quote.take().is_some() {
+                    self.quote_preview = None;
+                    app.set_view_id(ViewID::Compose {
+                        reply_to: self.reply_to.clone(),
+                        quote: None,
+                    });
+                }
+                self.clear();
+            }
+            Some(Err(err)) => app.report_error(err.context("failed to post")),
+            None => {}
+        }
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        match ev.code {
+            KeyCode::Esc => app.set_view_id(ViewID::Home),
+            KeyCode::Enter | KeyCode::Char('i') if !self.post_res.is_loading() => self.set_focus(),
+            KeyCode::Char('a') => self.open_attach(),
+            KeyCode::Backspace if !self.post_res.is_loading() => {
+                self.images.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_input(&mut self, input: Input, app: &mut App) {
+        if let Some(textarea) = &mut self.attach {
+            match input.key {
+                Key::Esc => self.attach = None,
+                Key::Enter => self.attach(app),
+                _ => {
+                    textarea.input(input);
+                }
+            }
+            return;
+        }
+        match input {
+            Input { key: Key::Esc, .. } => self.lose_focus(),
+            Input {
+                key: Key::Char('s'),
+                ctrl: true,
+                ..
+            } => self.post(app),
+            input => {
+                if self.textarea.input(input) {
+                    self.posted = false;
+                }
+            }
+        }
+    }
+
+    fn on_retry(&mut self, app: &mut App) {
+        self.post(app);
+    }
+
+    fn focus_in_textarea(&self) -> bool {
+        self.focus || self.attach.is_some()
+    }
+
+    fn is_animating(&self) -> bool {
+        self.post_res.is_loading()
+    }
+}
+
+fn strong_ref(post: &PostView) -> strong_ref::Main {
+    strong_ref::Main {
+        cid: post.cid.clone(),
+        uri: post.uri.clone(),
+    }
+}
+
+/// Refers to `parent` and the root of its thread.
+fn reply_ref(parent: &PostView) -> bsky::feed::post::ReplyRef {
+    let parent_ref = strong_ref(parent);
+    let root = match &parent.record {
+        records::Record::Known(records::KnownRecord::AppBskyFeedPost(record)) => {
+            record.reply.as_ref().map(|reply| reply.root.clone())
+        }
+        _ => None,
+    };
+    bsky::feed::post::ReplyRef {
+        root: root.unwrap_or_else(|| parent_ref.clone()),
+        parent: parent_ref,
+    }
+}
+
+/// Embeds `quote` and `images`, or both of them in `recordWithMedia`.
+fn embed(
+    quote: Option<&PostView>,
+    images: impl IntoIterator<Item = bsky::embed::images::Image>,
+) -> Option<Union<bsky::feed::post::RecordEmbedRefs>> {
+    use bsky::{embed::record_with_media::MainMediaRefs, feed::post::RecordEmbedRefs};
+
+    let record = quote.map(|post| bsky::embed::record::Main {
+        record: strong_ref(post),
+    });
+    let images = Some(bsky::embed::images::Main {
+        images: images.into_iter().collect(),
+    })
+    .filter(|main| !main.images.is_empty())
+    .map(Box::new);
+    let embed = match (record, images) {
+        (Some(record), Some(images)) => RecordEmbedRefs::AppBskyEmbedRecordWithMediaMain(Box::new(
+            bsky::embed::record_with_media::Main {
+                media: Union::Refs(MainMediaRefs::AppBskyEmbedImagesMain(images)),
+                record,
+            },
+        )),
+        (Some(record), None) => RecordEmbedRefs::AppBskyEmbedRecordMain(Box::new(record)),
+        (None, Some(images)) => RecordEmbedRefs::AppBskyEmbedImagesMain(images),
+        (None, None) => return None,
+    };
+    Some(Union::Refs(embed))
+}
+
+/// Counts user-perceived characters as the server does to limit the length of posts.
+fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(0, "")]
+#[case(5, "hello")]
+#[case(5, "こんにちは")]
+#[case(1, "👨‍👩‍👧")]
+#[case(2, "🇯🇵🇺🇸")]
+#[case(1, "e\u{301}")]
+fn test_grapheme_count(#[case] count: usize, #[case] text: &str) {
+    assert_eq!(count, grapheme_count(text));
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::xrpc::http::StatusCode;
+
+    use super::*;
+    use crate::{
+        app::{testing::Harness, EventHandler},
+        atp::fixture::FixtureClient,
+        tui::Event,
+    };
+
+    /// The post of Bob in the fixture timeline.
+    fn post_view() -> serde_json::Value {
+        FixtureClient::timeline()["feed"][0]["post"].clone()
+    }
+
+    fn strong_ref(uri: &str, cid: &str) -> strong_ref::Main {
+        strong_ref::Main {
+            cid: cid.parse().unwrap(),
+            uri: uri.to_string(),
+        }
+    }
+
+    fn bob() -> strong_ref::Main {
+        strong_ref(
+            "at://did:plc:bob/app.bsky.feed.post/3kwaosbrtgk2x",
+            "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+        )
+    }
+
+    #[test]
+    fn reply_to_root() {
+        let parent: PostView = serde_json::from_value(post_view()).unwrap();
+        let reply = reply_ref(&parent);
+        let bob = bob();
+        assert_eq!(bob, reply.root);
+        assert_eq!(bob, reply.parent);
+    }
+
+    #[test]
+    fn reply_to_reply() {
+        let root = strong_ref(
+            "at://did:plc:alice/app.bsky.feed.post/3kwaoqxyz2k2a",
+            "bafyreihq3gquqeo4dmzhmsaipxxsjkyaldcqaqkv7mmtclnvjhmpvpftby",
+        );
+        let mut view = post_view();
+        view["record"]["reply"] = serde_json::json!({ "root": root, "parent": root });
+        let parent: PostView = serde_json::from_value(view).unwrap();
+
+        let reply = reply_ref(&parent);
+        assert_eq!(root, reply.root);
+        assert_eq!(bob(), reply.parent);
+    }
+
+    async fn compose(client: FixtureClient) -> Harness {
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.app.view_id() == &ViewID::Home).await;
+        h.key(KeyCode::Char('n'));
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        h
+    }
+
+    #[tokio::test]
+    async fn publish() {
+        let mut h = compose(FixtureClient::default()).await;
+        h.type_str("Hello, world!");
+        assert!(h.screen().contains("13/300"));
+        h.ctrl('s');
+
+        h.wait_until(|h| h.screen().contains("Posted.")).await;
+        assert!(h.client.requested("com.atproto.repo.createRecord"));
+        assert!(h.screen().contains("0/300"));
+    }
+
+    #[tokio::test]
+    async fn reply() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('r'));
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        assert!(h.screen().contains("Replying to"));
+        assert!(h.screen().contains("Hello from the fixture timeline!"));
+
+        h.type_str("Hi, Bob!");
+        h.ctrl('s');
+        h.wait_until(|h| h.screen().contains("Posted.")).await;
+        assert!(h.client.requested("com.atproto.repo.createRecord"));
+        // Ready to write another reply
+        assert!(h.screen().contains("Replying to"));
+    }
+
+    #[tokio::test]
+    async fn keep_posting_after_leaving() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('n'));
+        h.type_str("Hello, world!");
+        h.ctrl('s');
+        // Reply to a post before the first one is published
+        h.key(KeyCode::Esc);
+        h.key(KeyCode::Char('r'));
+        h.wait_until(|h| h.screen().contains("Replying to")).await;
+        assert!(!h.screen().contains("Hello, world!"));
+
+        h.wait_until(|h| h.client.requested("com.atproto.repo.createRecord"))
+            .await;
+    }
+
+    #[test]
+    fn quote() {
+        let post: PostView = serde_json::from_value(post_view()).unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "$type": "app.bsky.embed.record",
+                "record": bob(),
+            }),
+            serde_json::to_value(embed(Some(&post), None)).unwrap()
+        );
+    }
+
+    fn image() -> bsky::embed::images::Image {
+        serde_json::from_value(serde_json::json!({
+            "alt": "",
+            "image": serde_json::from_str::<serde_json::Value>(
+                include_str!("../../atp/fixtures/upload_blob.json")
+            ).unwrap()["blob"],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn quote_with_images() {
+        let post: PostView = serde_json::from_value(post_view()).unwrap();
+        let images = serde_json::json!([image()]);
+        assert_eq!(
+            serde_json::json!({
+                "$type": "app.bsky.embed.recordWithMedia",
+                "record": { "record": bob() },
+                "media": {
+                    "$type": "app.bsky.embed.images",
+                    "images": images,
+                },
+            }),
+            serde_json::to_value(embed(Some(&post), [image()])).unwrap()
+        );
+        assert_eq!(
+            serde_json::json!({
+                "$type": "app.bsky.embed.images",
+                "images": images,
+            }),
+            serde_json::to_value(embed(None, [image()])).unwrap()
+        );
+        assert_eq!(None, embed(None, None));
+    }
+
+    async fn attach(h: &mut Harness, path: &std::path::Path) {
+        h.key(KeyCode::Esc);
+        h.key(KeyCode::Char('a'));
+        assert!(h.screen().contains("Image file"));
+        h.type_str(&path.display().to_string());
+        h.key(KeyCode::Enter);
+    }
+
+    #[tokio::test]
+    async fn publish_images() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cat.png");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n").unwrap();
+        let mut h = compose(FixtureClient::default()).await;
+        attach(&mut h, &path).await;
+        // Back to the editor to write about the image
+        assert!(h.view.focus_in_textarea());
+        h.wait_until(|h| h.screen().contains("cat.png") && !h.screen().contains("uploading…"))
+            .await;
+        assert!(h.client.requested("com.atproto.repo.uploadBlob"));
+
+        // Images may be posted without text
+        h.ctrl('s');
+        h.wait_until(|h| h.screen().contains("Posted.")).await;
+        assert!(h.client.requested("com.atproto.repo.createRecord"));
+        assert!(!h.screen().contains("cat.png"));
+    }
+
+    #[tokio::test]
+    async fn attach_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut h = compose(FixtureClient::default()).await;
+        attach(&mut h, &dir.path().join("missing.png")).await;
+
+        h.wait_until(|h| h.screen().contains("failed to attach the image"))
+            .await;
+        // The error names the file, but it is no longer attached
+        assert!(!h.screen().contains("uploading…"));
+        assert!(!h.screen().contains("Backspace: remove"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn quote_without_text() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('q'));
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        assert!(h.screen().contains("Hello from the fixture timeline!"));
+
+        h.ctrl('s');
+        h.wait_until(|h| h.screen().contains("Posted.")).await;
+        assert!(h.client.requested("com.atproto.repo.createRecord"));
+        assert!(!h.screen().contains("Hello from the fixture timeline!"));
+
+        // Nothing left to post
+        h.ctrl('s');
+        // A request would be sent before the paused clock moves on
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        h.send(Event::Tick);
+        assert_eq!(1, h.client.requested_times("com.atproto.repo.createRecord"));
+        assert!(h.screen().contains("Posted."));
+    }
+
+    #[tokio::test]
+    async fn too_long() {
+        let mut h = compose(FixtureClient::default()).await;
+        h.type_str(&"a".repeat(MAX_GRAPHEMES + 1));
+        h.ctrl('s');
+
+        h.send(Event::Tick);
+        assert!(h.screen().contains("301/300"));
+        assert!(h.screen().contains("The post is too long."));
+        assert!(!h.client.requested("com.atproto.repo.createRecord"));
+    }
+
+    #[tokio::test]
+    async fn failure() {
+        let client = FixtureClient::default().xrpc_error(
+            "com.atproto.repo.createRecord",
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+        );
+        let mut h = compose(client).await;
+        h.type_str("Hello, world!");
+        h.ctrl('s');
+
+        h.wait_until(|h| h.screen().contains("failed to post"))
+            .await;
+        // The text is kept to retry
+        assert!(h.screen().contains("Hello, world!"));
+    }
+}
@@ -1,100 +1,301 @@
-use std::cell::RefCell;
-
-use atrium_api::app::bsky;
 use ratatui::{prelude::*, widgets::*};
+use tui_textarea::Key;
 
 use crate::{
-    atp::Response,
     prelude::*,
-    widgets::{atoms::Spinner, Posts, PostsState},
+    widgets::{atoms::TextArea, organisms::Feed, ViewID},
 };
 
 #[derive(Debug, Default)]
 pub struct Home {
-    posts: Posts,
-    posts_state: RefCell<PostsState>,
-    response: Response<crate::atp::GetTimelineResult>,
-    /// Used to get old posts
-    post_cursor: Option<String>,
+    feed: Feed,
+    /// The prompt for a handle to open the profile of
+    lookup: Option<TextArea<'static>>,
 }
 
 impl Home {
-    pub fn get_timeline_params(&self) -> bsky::feed::get_timeline::Parameters {
-        bsky::feed::get_timeline::Parameters {
-            algorithm: None,
-            cursor: self.post_cursor.clone(),
-            limit: 15.try_into().ok(),
-        }
+    fn open_lookup(&mut self) {
+        let mut textarea = TextArea::new(" Handle ", false);
+        textarea.set_focus();
+        self.lookup = Some(textarea);
     }
 
-    pub fn recv_timeline(&mut self, timeline: bsky::feed::get_timeline::Output) {
-        self.post_cursor = timeline.cursor;
-        for post in timeline.feed {
-            self.posts.add_post(post, false);
+    /// Opens the profile of the handle in the prompt.
+    fn lookup(&mut self, app: &mut App) {
+        let Some(textarea) = self.lookup.take() else {
+            return;
+        };
+        let actor = textarea.lines()[0].trim().to_string();
+        if !actor.is_empty() {
+            app.set_view_id(ViewID::Profile { actor });
         }
     }
-
-    pub fn scroll_up(&mut self) {
-        self.posts.scroll = self.posts.scroll.saturating_sub(1);
-    }
-
-    pub fn scroll_down(&mut self) {
-        self.posts.scroll += 1;
-    }
 }
 
 impl WidgetRef for Home {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let [_, posts_area, _] = Layout::horizontal([
+        let [_, feed_area, _] = Layout::horizontal([
             Constraint::Fill(1),
             Constraint::Fill(5),
             Constraint::Fill(1),
         ])
         .areas(area);
+        self.feed.render_ref(feed_area, buf);
 
-        // posts
-        let mut posts_state = self.posts_state.borrow_mut();
-        self.posts.render_ref(posts_area, buf, &mut posts_state);
-
-        // spinner
-        let Some(blank_height) = posts_state.blank_height else {
-            return;
-        };
-        let blank_area = Rect {
-            height: blank_height,
-            y: posts_area.height - blank_height,
-            ..posts_area
-        };
-        let [_, spinner_area] = Layout::vertical([
-            Constraint::Length(14_u16.saturating_sub(blank_area.height)),
-            Constraint::Fill(1),
-        ])
-        .areas(blank_area);
-        Spinner::new().render_ref(spinner_area, buf);
+        if let Some(textarea) = &self.lookup {
+            let [_, lookup_area, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(3),
+                Constraint::Fill(1),
+            ])
+            .areas(feed_area);
+            Clear.render(lookup_area, buf);
+            textarea.widget().render(lookup_area, buf);
+        }
     }
 }
 
 impl crate::app::EventHandler for Home {
     fn on_render(&mut self, app: &mut App) {
-        if self.posts_state.borrow().blank_height.is_some() && self.response.is_empty() {
-            self.response = app.atp.get_timeline(self.get_timeline_params());
-        }
+        self.feed.on_render(app);
+    }
 
-        if let Some(Ok(timeline)) = self.response.take_data() {
-            self.recv_timeline(timeline);
-        }
+    fn on_retry(&mut self, app: &mut App) {
+        self.feed.on_retry(app);
     }
 
     fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
-        if ev.code == KeyCode::Esc {
-            app.exit();
-            return;
+        if !self.feed.is_confirming() {
+            match ev.code {
+                KeyCode::Esc => {
+                    app.exit();
+                    return;
+                }
+                KeyCode::Char('n') => {
+                    app.set_view_id(ViewID::Compose {
+                        reply_to: None,
+                        quote: None,
+                    });
+                    return;
+                }
+                KeyCode::Char('@') => {
+                    self.open_lookup();
+                    return;
+                }
+                _ => {}
+            }
         }
-        if ev.code == KeyCode::Char('k') {
-            self.scroll_up();
+        self.feed.on_key(ev, app);
+    }
+
+    fn on_input(&mut self, input: tui_textarea::Input, app: &mut App) {
+        match input.key {
+            Key::Esc => self.lookup = None,
+            Key::Enter => self.lookup(app),
+            _ => {
+                if let Some(textarea) = &mut self.lookup {
+                    textarea.input(input);
+                }
+            }
         }
-        if ev.code == KeyCode::Char('j') {
-            self.scroll_down();
+    }
+
+    fn focus_in_textarea(&self) -> bool {
+        self.lookup.is_some()
+    }
+
+    fn is_animating(&self) -> bool {
+        self.feed.is_animating()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::xrpc::http::StatusCode;
+
+    use super::*;
+    use crate::{app::testing::Harness, atp::fixture::FixtureClient};
+
+    #[tokio::test]
+    async fn timeline() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        assert_eq!(&ViewID::Home, h.app.view_id());
+        assert!(h.screen().contains("Bob"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeline_end() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        // The paused clock advances only after the spawned requests have gone as far as they can
+        for _ in 0..5 {
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            h.send(TuiEvent::Tick);
         }
+        // The fixture timeline has no cursor, so the space below it stays blank
+        assert_eq!(1, h.client.requested_times("app.bsky.feed.getTimeline"));
+    }
+
+    /// Lets Alice have liked and reposted the post of Bob.
+    fn react(timeline: &mut serde_json::Value) {
+        timeline["feed"][0]["post"]["viewer"] = serde_json::json!({
+            "like": "at://did:plc:alice/app.bsky.feed.like/3kwapw2bqjs2a",
+            "repost": "at://did:plc:alice/app.bsky.feed.repost/3kwapw2bqjs2b",
+        });
+    }
+
+    #[tokio::test]
+    async fn like() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_until(|h| h.screen().contains("♡ 3")).await;
+        h.key(KeyCode::Char('l'));
+        // Updated before the request is done
+        assert!(h.screen().contains("♥ 4"));
+
+        h.wait_until(|h| h.client.requested("com.atproto.repo.createRecord"))
+            .await;
+        h.send(TuiEvent::Tick);
+        assert!(h.app.error().is_none());
+        assert!(h.screen().contains("♥ 4"));
+    }
+
+    #[tokio::test]
+    async fn like_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "com.atproto.repo.createRecord",
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+        );
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.screen().contains("♡ 3")).await;
+        h.key(KeyCode::Char('l'));
+        assert!(h.screen().contains("♥ 4"));
+
+        // Rolled back
+        h.wait_until(|h| h.screen().contains("failed to update the like"))
+            .await;
+        assert!(h.screen().contains("♡ 3"));
+    }
+
+    #[tokio::test]
+    async fn unlike_and_unrepost() {
+        let client = FixtureClient::default().with_timeline(react);
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.screen().contains("♥ 3")).await;
+        h.key(KeyCode::Char('l'));
+        h.key(KeyCode::Char('t'));
+        assert!(h.screen().contains("♡ 2"));
+
+        h.wait_until(|h| h.client.requested_times("com.atproto.repo.deleteRecord") == 2)
+            .await;
+        h.send(TuiEvent::Tick);
+        assert_eq!(None, h.app.error());
+        assert!(!h.client.requested("com.atproto.repo.createRecord"));
+    }
+
+    /// Lets the post of Bob have been written by Alice.
+    fn own(timeline: &mut serde_json::Value) {
+        timeline["feed"][0]["post"]["uri"] =
+            "at://did:plc:alice/app.bsky.feed.post/3kwaosbrtgk2x".into();
+        timeline["feed"][0]["post"]["author"] = serde_json::json!({
+            "did": "did:plc:alice",
+            "handle": "alice.test",
+        });
+    }
+
+    #[tokio::test]
+    async fn delete_post() {
+        let client = FixtureClient::default().with_timeline(own);
+        let mut h = Harness::signed_in(client).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('d'));
+        assert!(h.screen().contains("Delete this post?"));
+        h.key(KeyCode::Char('y'));
+        assert!(h.screen().contains("Deleting…"));
+
+        h.wait_until(|h| !h.screen().contains("Hello from the fixture timeline!"))
+            .await;
+        assert!(h.client.requested("com.atproto.repo.deleteRecord"));
+        assert_eq!(None, h.app.error());
+    }
+
+    #[tokio::test]
+    async fn delete_reposted_post() {
+        let client = FixtureClient::default().with_timeline(|timeline| {
+            own(timeline);
+            let mut repost = timeline["feed"][0].clone();
+            repost["reason"] = serde_json::json!({
+                "$type": "app.bsky.feed.defs#reasonRepost",
+                "by": { "did": "did:plc:bob", "handle": "bob.test" },
+                "indexedAt": "2024-07-01T00:00:00.000Z",
+            });
+            timeline["feed"] = serde_json::json!([timeline["feed"][0], repost]);
+        });
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.screen().contains("Reposted by")).await;
+        h.key(KeyCode::Char('d'));
+        h.key(KeyCode::Char('y'));
+        assert_eq!(2, h.screen().matches("Deleting…").count());
+
+        // The repost goes away with the post
+        h.wait_until(|h| !h.screen().contains("Hello from the fixture timeline!"))
+            .await;
+        assert_eq!(None, h.app.error());
+    }
+
+    #[tokio::test]
+    async fn delete_post_error() {
+        let client = FixtureClient::default().with_timeline(own).xrpc_error(
+            "com.atproto.repo.deleteRecord",
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+        );
+        let mut h = Harness::signed_in(client).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('d'));
+        h.key(KeyCode::Char('y'));
+
+        h.wait_until(|h| h.screen().contains("failed to delete the post"))
+            .await;
+        assert!(h.screen().contains("Hello from the fixture timeline!"));
+        assert!(!h.screen().contains("Deleting…"));
+    }
+
+    #[tokio::test]
+    async fn cancel_delete() {
+        let client = FixtureClient::default().with_timeline(own);
+        let mut h = Harness::signed_in(client).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('d'));
+        h.key(KeyCode::Char('n'));
+        assert!(!h.screen().contains("Delete this post?"));
+        assert!(!h.client.requested("com.atproto.repo.deleteRecord"));
+    }
+
+    #[tokio::test]
+    async fn delete_others_post() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('d'));
+        assert!(!h.screen().contains("Delete this post?"));
+    }
+
+    #[tokio::test]
+    async fn timeline_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "app.bsky.feed.getTimeline",
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalServerError",
+        );
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.screen().contains("failed to get the timeline"))
+            .await;
+
+        // Retrying sends the request again
+        let sent = h.client.requested_times("app.bsky.feed.getTimeline");
+        h.key(KeyCode::Char('r'));
+        h.wait_until(|h| h.client.requested_times("app.bsky.feed.getTimeline") > sent)
+            .await;
     }
 }
@@ -1,10 +1,12 @@
 use crossterm::event::KeyCode;
 use ratatui::{prelude::*, widgets::*};
+#[cfg(test)]
+use rstest::rstest;
 use tui_textarea::Key;
 
 use crate::{
     app::App,
-    atp::Response,
+    atp::{LoginError, Response},
     widgets::{
         atoms::{Spinner, TextArea},
         ViewID,
@@ -13,17 +15,20 @@ use crate::{
 
 #[derive(Debug)]
 pub struct Login {
-    textareas: [TextArea<'static>; 2],
+    /// The handle, the password, and the email code if the server requires it.
+    textareas: Vec<TextArea<'static>>,
     focus: Option<usize>,
     login_res: Response<crate::atp::LoginResult>,
     resume_session_res: Response<crate::atp::ResumeSessionResult>,
     resume_session_failed: bool,
+    endpoint: String,
+    rate_limit_wait: Option<std::time::Duration>,
 }
 
 impl Default for Login {
     fn default() -> Self {
         Self {
-            textareas: [
+            textareas: vec![
                 TextArea::new(" Handle name or Email address ", false),
                 TextArea::new(" Password ", true),
             ],
@@ -31,6 +36,8 @@ impl Default for Login {
             login_res: Response::empty(),
             resume_session_res: Response::empty(),
             resume_session_failed: false,
+            endpoint: String::new(),
+            rate_limit_wait: None,
         }
     }
 }
@@ -42,6 +49,22 @@ impl Login {
     pub fn passwd(&self) -> String {
         self.textareas[1].lines()[0].to_string()
     }
+    pub fn auth_factor_token(&self) -> Option<String> {
+        self.textareas
+            .get(2)
+            .map(|t| t.lines()[0].trim().to_string())
+    }
+
+    fn require_auth_factor_token(&mut self) {
+        if self.textareas.len() < 3 {
+            self.textareas.push(TextArea::new(" Email code ", false));
+        }
+    }
+
+    fn login(&mut self, app: &mut App) {
+        self.login_res = app.login(self.ident(), self.passwd(), self.auth_factor_token());
+        self.lose_focus();
+    }
 
     pub fn textarea(&mut self) -> Option<&mut TextArea<'static>> {
         self.focus.map(|n| &mut self.textareas[n])
@@ -51,18 +74,14 @@ impl Login {
         if self.login_res.is_loading() {
             return;
         }
-        if let Some(n) = self.focus {
-            self.textareas[n].lose_focus();
-        }
-        self.focus = match self.focus {
-            Some(1) => Some(0),
-            Some(0) => Some(1),
-            None => Some(0),
-            _ => unreachable!(),
-        };
-        if let Some(n) = self.focus {
-            self.textareas[n].set_focus();
-        }
+        let n = self.focus.map_or(0, |n| (n + 1) % self.textareas.len());
+        self.set_focus(n);
+    }
+
+    fn set_focus(&mut self, n: usize) {
+        self.lose_focus();
+        self.focus = Some(n);
+        self.textareas[n].set_focus();
     }
 
     pub fn lose_focus(&mut self) {
@@ -85,50 +104,98 @@ impl WidgetRef for Login {
             Constraint::Percentage(30),
         ])
         .areas(area);
-        let [_, ident, passwd, spinner, _] = Layout::vertical([
+        let [_, endpoint, ident, passwd, token, message, spinner, _] = Layout::vertical([
             Constraint::Percentage(30),
+            Constraint::Length(1),
             Constraint::Length(3),
             Constraint::Length(3),
+            Constraint::Length(if self.textareas.len() > 2 { 3 } else { 0 }),
+            Constraint::Length(1),
             Constraint::Length(3),
             Constraint::Fill(1),
         ])
         .spacing(1)
         .areas(area);
 
+        Line::from_iter([
+            " Server: ".dim(),
+            self.endpoint
+                .split_once("://")
+                .map_or(self.endpoint.as_str(), |(_, host)| host)
+                .into(),
+        ])
+        .render(endpoint, buf);
         self.textareas[0].widget().render(ident, buf);
         self.textareas[1].widget().render(passwd, buf);
+        if let Some(textarea) = self.textareas.get(2) {
+            textarea.widget().render(token, buf);
+            Line::from(" Enter the sign-in code sent to your email address.".yellow())
+                .render(message, buf);
+        } else if !self.passwd().is_empty() && !looks_like_app_password(&self.passwd()) {
+            Line::from(
+                " ⚠ This does not look like an app password. \
+                 Using an app password is recommended."
+                    .yellow(),
+            )
+            .render(message, buf);
+        }
         if self.login_res.is_loading() || self.resume_session_res.is_loading() {
-            Spinner::new().render(spinner, buf);
+            Spinner::new()
+                .rate_limit_wait(self.rate_limit_wait)
+                .render_ref(spinner, buf);
         }
     }
 }
 
 impl crate::app::EventHandler for Login {
     fn on_render(&mut self, app: &mut App) {
+        self.endpoint = app.atp().account().endpoint;
+        self.rate_limit_wait = app.rate_limit().waiting_for();
         if app.view_id().login_resume_session()
             && self.resume_session_res.is_empty()
             && !self.resume_session_failed
         {
-            self.resume_session_res = app.atp.resume_session();
+            self.resume_session_res = app.atp().resume_session();
         } else if let Some(result) = self.resume_session_res.take_data() {
-            if result.is_ok() {
-                app.set_view_id(ViewID::Home);
-            } else {
-                self.resume_session_failed = true;
-                self.switch_focus();
+            match result {
+                Ok(()) => app.set_view_id(ViewID::Home),
+                Err(err) => {
+                    self.resume_session_failed = true;
+                    // Nothing to resume if the account has never signed in
+                    if app.atp().account().did.is_some() {
+                        // Leaves the keys to the toast to retry or dismiss
+                        app.report_error(err.context("failed to resume the session"));
+                    } else {
+                        self.switch_focus();
+                    }
+                }
             }
         } else if let Some(result) = self.login_res.take_data() {
-            if result.is_ok() {
-                app.set_view_id(ViewID::Home);
-            } else {
-                self.switch_focus();
+            match result {
+                Ok(()) => {
+                    app.signed_in();
+                    app.set_view_id(ViewID::Home);
+                }
+                Err(err) => {
+                    if let Some(LoginError::AuthFactorTokenRequired) = err.downcast_ref() {
+                        self.require_auth_factor_token();
+                        self.set_focus(2);
+                    } else {
+                        app.report_error(err.context("failed to sign in"));
+                    }
+                }
             }
         }
     }
 
     fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
         if ev.code == KeyCode::Esc {
-            app.exit();
+            if self.login_res.is_loading() {
+                self.login_res.cancel();
+                self.switch_focus();
+            } else {
+                app.exit();
+            }
             return;
         }
         if self.resume_session_res.is_loading() {
@@ -145,14 +212,147 @@ impl crate::app::EventHandler for Login {
         } else if input.key == Key::Tab {
             self.switch_focus();
         } else if input.key == Key::Enter && self.login_res.is_empty() {
-            self.login_res = app.atp.login(self.ident(), self.passwd());
-            self.lose_focus();
+            self.login(app);
         } else if let Some(ref mut textarea) = self.textarea() {
             textarea.input(input);
         }
     }
 
+    fn on_retry(&mut self, app: &mut App) {
+        if self.resume_session_failed && self.ident().is_empty() {
+            self.lose_focus();
+            self.resume_session_failed = false;
+        } else if self.login_res.is_empty() {
+            self.login(app);
+        }
+    }
+
     fn focus_in_textarea(&self) -> bool {
         self.has_focus()
     }
+
+    fn is_animating(&self) -> bool {
+        self.login_res.is_loading() || self.resume_session_res.is_loading()
+    }
+}
+
+/// App passwords are formatted as `xxxx-xxxx-xxxx-xxxx`.
+fn looks_like_app_password(passwd: &str) -> bool {
+    let groups: Vec<&str> = passwd.split('-').collect();
+    groups.len() == 4
+        && groups.iter().all(|g| {
+            g.len() == 4
+                && g.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        })
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(true, "abcd-efgh-1234-5678")]
+#[case(false, "hunter2")]
+#[case(false, "abcd-efgh-1234")]
+#[case(false, "abcd-efgh-1234-567")]
+#[case(false, "ABCD-EFGH-1234-5678")]
+#[case(false, "abcd-efgh-1234-5678-")]
+fn test_looks_like_app_password(#[case] result: bool, #[case] passwd: &str) {
+    assert_eq!(result, looks_like_app_password(passwd));
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::xrpc::http::StatusCode;
+
+    use super::*;
+    use crate::{
+        app::{testing::Harness, EventHandler},
+        atp::fixture::FixtureClient,
+    };
+
+    #[tokio::test]
+    async fn sign_in() {
+        let mut h = Harness::new(FixtureClient::default()).await;
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        h.type_str("alice.test");
+        h.key(KeyCode::Tab);
+        h.type_str("abcd-efgh-ijkl-mnop");
+        h.key(KeyCode::Enter);
+
+        h.wait_for_timeline().await;
+        assert_eq!(&ViewID::Home, h.app.view_id());
+        assert!(h.screen().contains("3. @alice.test"));
+        assert!(h.client.requests().contains(&String::from(
+            "POST https://pds.example.com/xrpc/com.atproto.server.createSession"
+        )));
+    }
+
+    #[tokio::test]
+    async fn sign_in_with_email_code() {
+        let client = FixtureClient::default().xrpc_error(
+            "com.atproto.server.createSession",
+            StatusCode::UNAUTHORIZED,
+            "AuthFactorTokenRequired",
+        );
+        let mut h = Harness::new(client).await;
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        h.type_str("alice.test");
+        h.key(KeyCode::Tab);
+        h.type_str("hunter2");
+        h.key(KeyCode::Enter);
+
+        h.wait_until(|h| h.screen().contains("Email code")).await;
+        assert!(h.app.error().is_none());
+        assert!(h.view.focus_in_textarea());
+    }
+
+    #[tokio::test]
+    async fn sign_in_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "com.atproto.server.createSession",
+            StatusCode::UNAUTHORIZED,
+            "AuthenticationRequired",
+        );
+        let mut h = Harness::new(client).await;
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        h.type_str("alice.test");
+        h.key(KeyCode::Enter);
+
+        h.wait_until(|h| h.screen().contains("failed to sign in"))
+            .await;
+        assert!(matches!(h.app.view_id(), ViewID::Login { .. }));
+        assert!(!h.client.requested("app.bsky.feed.getTimeline"));
+    }
+
+    #[tokio::test]
+    async fn retry_sign_in() {
+        let client = FixtureClient::default().xrpc_error(
+            "com.atproto.server.createSession",
+            StatusCode::UNAUTHORIZED,
+            "AuthenticationRequired",
+        );
+        let mut h = Harness::new(client).await;
+        h.wait_until(|h| h.view.focus_in_textarea()).await;
+        h.type_str("alice.test");
+        h.key(KeyCode::Enter);
+        h.wait_until(|h| h.screen().contains("failed to sign in"))
+            .await;
+        assert_eq!(
+            1,
+            h.client.requested_times("com.atproto.server.createSession")
+        );
+
+        h.key(KeyCode::Char('r'));
+        assert_eq!(None, h.app.error());
+        // Not typed into the handle
+        assert!(!h.screen().contains("alice.testr"));
+        h.wait_until(|h| h.client.requested_times("com.atproto.server.createSession") == 2)
+            .await;
+
+        // Back to the form once the error is dismissed
+        h.wait_until(|h| h.screen().contains("failed to sign in"))
+            .await;
+        h.key(KeyCode::Char('x'));
+        h.key(KeyCode::Tab);
+        assert!(h.view.focus_in_textarea());
+    }
 }
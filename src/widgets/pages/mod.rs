@@ -1,5 +1,21 @@
+mod accounts;
+mod actors;
+mod compose;
 mod home;
 mod login;
+mod notifications;
+mod profile;
+mod search;
+mod thread;
+mod unlock;
 
+pub use accounts::Accounts;
+pub use actors::Actors;
+pub use compose::Compose;
 pub use home::Home;
 pub use login::Login;
+pub use notifications::Notifications;
+pub use profile::Profile;
+pub use search::Search;
+pub use thread::Thread;
+pub use unlock::Unlock;
@@ -0,0 +1,407 @@
+mod group;
+
+use std::{
+    cell::{Cell, RefCell},
+    ops::Range,
+};
+
+use atrium_api::{
+    app::bsky::notification::list_notifications::Notification, records, types::string::Datetime,
+};
+use ratatui::{prelude::*, widgets::*};
+
+use self::group::Group;
+use crate::{
+    atp::{Response, StreamResponse},
+    prelude::*,
+    widgets::{
+        atoms::{BlockExt, Spinner, Text},
+        post_content, scroll_to, Store, Storeable, ViewID, BORDER, SELECTED_BORDER,
+    },
+};
+
+/// Lists what others have done to the account, the newest first.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    /// The notifications merged by their subjects
+    rows: Vec<Group>,
+    selected: usize,
+    scroll: u16,
+    /// Pages of the notifications from the cursor
+    response: StreamResponse<crate::atp::NotificationsResult>,
+    cursor: Option<String>,
+    /// Stops fetching notifications until the user retries
+    failed: bool,
+    /// There are no older notifications to fetch
+    reached_end: bool,
+    /// When the page has been opened, to mark the notifications until then as read
+    opened_at: Option<Datetime>,
+    marked_seen: bool,
+    update_seen_res: Response<crate::atp::UpdateSeenResult>,
+    /// The height of the viewport in the last render.
+    height: Cell<u16>,
+    /// The vertical ranges of the rows in the last render, without scrolling.
+    row_ranges: RefCell<Vec<Range<u16>>>,
+}
+
+impl Notifications {
+    /// Whether the rows do not fill the screen below the scroll position.
+    fn has_space(&self) -> bool {
+        let bottom = self.row_ranges.borrow().last().map_or(0, |range| range.end);
+        bottom <= self.scroll + self.height.get()
+    }
+
+    /// Selects the row at `index`, scrolling to show it as much as possible.
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().saturating_sub(1));
+        if let Some(range) = self.row_ranges.get_mut().get(self.selected) {
+            self.scroll = scroll_to(self.scroll, range, self.height.get());
+        }
+    }
+
+    fn mark_seen(&mut self, app: &App) {
+        if self.marked_seen {
+            return;
+        }
+        if let Some(seen_at) = self.opened_at.clone() {
+            self.update_seen_res = app.atp().update_seen(seen_at);
+            self.marked_seen = true;
+        }
+    }
+}
+
+/// The view to open for a notification.
+fn target(notification: &Notification) -> Option<ViewID> {
+    match notification.reason.as_str() {
+        "follow" => Some(ViewID::Profile {
+            actor: notification.author.did.as_str().to_string(),
+        }),
+        "like" | "repost" => notification
+            .reason_subject
+            .clone()
+            .map(|uri| ViewID::Thread { uri }),
+        "mention" | "reply" | "quote" => Some(ViewID::Thread {
+            uri: notification.uri.clone(),
+        }),
+        _ => None,
+    }
+}
+
+impl WidgetRef for Notifications {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.height.set(area.height);
+        let mut row_ranges = self.row_ranges.borrow_mut();
+        row_ranges.clear();
+        let mut store = Store::new().scroll_v(self.scroll as i32);
+        for (i, row) in self.rows.iter().enumerate() {
+            let space = store.bottom_space(area.height(u16::MAX));
+            let block = if i == self.selected {
+                Block::new()
+                    .borders(Borders::LEFT | Borders::BOTTOM)
+                    .border_set(SELECTED_BORDER)
+                    .border_style(Style::new().blue())
+            } else {
+                Block::new()
+                    .borders(Borders::LEFT | Borders::BOTTOM)
+                    .border_set(BORDER)
+                    .border_style(Style::new().blue().dim())
+            };
+            block
+                .padding(Padding::left(1))
+                .wrap(move |inner, s| store_row(row, inner, s))
+                .fit_vertical()
+                .store(space, &mut store);
+            row_ranges.push(space.y - area.y..store.stored_area().bottom() - area.y);
+        }
+        store.render_ref(area, buf);
+
+        let bottom = row_ranges.last().map_or(0, |range| range.end);
+        if bottom <= self.scroll + area.height && self.response.is_loading() {
+            let [_, spinner_area] = Layout::vertical([
+                Constraint::Length(bottom.saturating_sub(self.scroll)),
+                Constraint::Fill(1),
+            ])
+            .areas(area);
+            Spinner::new().render_ref(spinner_area, buf);
+        }
+    }
+}
+
+fn store_row<'a>(row: &'a Group, area: Rect, store: &mut Store<'a>) {
+    let first = row.first();
+    let (icon, action) = match first.reason.as_str() {
+        "like" => ("♥".red(), "liked your post"),
+        "repost" => ("\u{f46a}".green(), "reposted your post"),
+        "follow" => ("+".blue(), "followed you"),
+        "mention" => ("@".light_blue(), "mentioned you"),
+        "reply" => ("\u{f41f}".blue(), "replied to you"),
+        "quote" => ("❝".blue(), "quoted your post"),
+        reason => ("?".dim(), reason),
+    };
+    let mut spans = vec![
+        if row.is_read() {
+            "  ".into()
+        } else {
+            "● ".blue()
+        },
+        icon,
+        "  ".into(),
+        row.names().bold(),
+        format!(" {action}").into(),
+    ];
+    if row.items.len() > 1 {
+        spans.push(if row.expanded { " ▾" } else { " ▸" }.dim());
+    }
+    Text::from_iter(spans).store(store.bottom_space(area), store);
+
+    if row.expanded {
+        for item in &row.items {
+            Text::from_iter([
+                "     ".into(),
+                group::name(item).to_string().into(),
+                format!("  @{}", item.author.handle.as_str()).dim(),
+            ])
+            .store(store.bottom_space(area).height(1), store);
+        }
+    }
+
+    // The text of the post for the notifications about a post by others
+    if !matches!(first.reason.as_str(), "mention" | "reply" | "quote") {
+        return;
+    }
+    if let records::Record::Known(records::KnownRecord::AppBskyFeedPost(post)) = &first.record {
+        Block::new()
+            .padding(Padding::left(5))
+            .wrap_child(post_content(post))
+            .fit_vertical()
+            .store(store.bottom_space(area), store);
+    }
+}
+
+impl crate::app::EventHandler for Notifications {
+    fn on_render(&mut self, app: &mut App) {
+        self.opened_at.get_or_insert_with(Datetime::now);
+        if let Some(Err(err)) = self.update_seen_res.take_data() {
+            app.report_error(err.context("failed to mark the notifications as read"));
+        }
+        // Take the next page only when there is space to show it
+        if !self.has_space() || self.failed || self.reached_end {
+            return;
+        }
+        if self.response.is_empty() {
+            self.response = app.atp().get_notifications(self.cursor.clone());
+        }
+        match self.response.take_data() {
+            Some(Ok(page)) => {
+                self.reached_end = page.cursor.is_none();
+                self.cursor = page.cursor;
+                group::group(&mut self.rows, page.items);
+                self.mark_seen(app);
+            }
+            Some(Err(err)) => {
+                self.failed = true;
+                app.report_error(err.context("failed to get the notifications"));
+            }
+            None => {}
+        }
+    }
+
+    fn on_retry(&mut self, _app: &mut App) {
+        self.failed = false;
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        match ev.code {
+            KeyCode::Esc => app.set_view_id(ViewID::Home),
+            KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Char('j') => self.scroll += 1,
+            KeyCode::Up => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down => self.select(self.selected + 1),
+            KeyCode::Enter => {
+                let row = self.rows.get(self.selected);
+                if let Some(view_id) = row.and_then(|row| target(row.first())) {
+                    app.set_view_id(view_id);
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(row) = self.rows.get_mut(self.selected) {
+                    row.expanded = !row.expanded && row.items.len() > 1;
+                }
+            }
+            KeyCode::Char('p') => {
+                if let Some(row) = self.rows.get(self.selected) {
+                    app.set_view_id(ViewID::Profile {
+                        actor: row.first().author.did.as_str().to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_animating(&self) -> bool {
+        self.response.is_loading()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::xrpc::http::StatusCode;
+
+    use super::*;
+    use crate::{app::testing::Harness, atp::fixture::FixtureClient};
+
+    #[tokio::test]
+    async fn notifications() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('4'));
+
+        h.wait_until(|h| h.screen().contains("This is worth reading."))
+            .await;
+        assert_eq!(&ViewID::Notifications, h.app.view_id());
+        let screen = h.screen();
+        assert!(screen.contains("● ♥  Bob liked your post"));
+        assert!(screen.contains("carol.test reposted your post"));
+        assert!(screen.contains("Dave followed you"));
+        assert!(screen.contains("   @  Bob mentioned you"));
+        assert!(screen.contains("Have you met @alice.test?"));
+        assert!(screen.contains("carol.test replied to you"));
+        assert!(screen.contains("Welcome aboard!"));
+        assert!(screen.contains("Dave quoted your post"));
+
+        h.wait_until(|h| h.client.requested("app.bsky.notification.updateSeen"))
+            .await;
+        h.send(TuiEvent::Tick);
+        assert_eq!(None, h.app.error());
+    }
+
+    /// The fixture notifications with more likes on the same post.
+    /// Lets three more accounts have liked the same post as Bob.
+    fn more_likes(output: &mut serde_json::Value) {
+        let like = output["notifications"][0].clone();
+        let notifications = output["notifications"].as_array_mut().unwrap();
+        for (i, author) in ["carol", "dave", "erin"].into_iter().enumerate() {
+            let mut like = like.clone();
+            like["author"] = serde_json::json!({
+                "did": format!("did:plc:{author}"),
+                "handle": format!("{author}.test"),
+            });
+            notifications.insert(i + 1, like);
+        }
+    }
+
+    #[tokio::test]
+    async fn group_likes() {
+        let client = FixtureClient::default()
+            .patch_xrpc("app.bsky.notification.listNotifications", more_likes);
+        let mut h = Harness::signed_in(client).await;
+        h.key(KeyCode::Char('4'));
+
+        h.wait_until(|h| h.screen().contains("Dave followed you"))
+            .await;
+        assert!(h
+            .screen()
+            .contains("Bob, carol.test and 2 others liked your post ▸"));
+        assert!(!h.screen().contains("@erin.test"));
+
+        h.key(KeyCode::Char('e'));
+        let screen = h.screen();
+        assert!(screen.contains("liked your post ▾"));
+        assert!(screen.contains("Bob  @bob.test"));
+        assert!(screen.contains("erin.test  @erin.test"));
+
+        h.key(KeyCode::Char('e'));
+        assert!(!h.screen().contains("@erin.test"));
+    }
+
+    #[tokio::test]
+    async fn open_notification() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('4'));
+        h.wait_until(|h| h.screen().contains("Dave followed you"))
+            .await;
+
+        h.key(KeyCode::Enter);
+        h.send(TuiEvent::Tick);
+        assert_eq!(
+            &ViewID::Thread {
+                uri: String::from("at://did:plc:alice/app.bsky.feed.post/3kwaoqxyz2k2a")
+            },
+            h.app.view_id()
+        );
+
+        h.key(KeyCode::Char('4'));
+        h.wait_until(|h| h.screen().contains("Dave followed you"))
+            .await;
+        h.key(KeyCode::Down);
+        h.key(KeyCode::Down);
+        h.key(KeyCode::Enter);
+        h.send(TuiEvent::Tick);
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("did:plc:dave")
+            },
+            h.app.view_id()
+        );
+    }
+
+    /// The badge wraps below the text of the tab on the narrow screen.
+    fn has_badge(h: &Harness) -> bool {
+        h.screen().lines().any(|line| line.trim_end() == " 3")
+    }
+
+    #[tokio::test]
+    async fn unread_badge() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_until(has_badge).await;
+        assert!(h.client.requested("app.bsky.notification.getUnreadCount"));
+
+        // Cleared once the notifications are read
+        h.key(KeyCode::Char('4'));
+        h.wait_until(|h| h.screen().contains("Dave followed you"))
+            .await;
+        assert!(!has_badge(&h));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_unread_badge() {
+        let start = tokio::time::Instant::now();
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_until(has_badge).await;
+        let checks = |h: &Harness| {
+            h.client
+                .requested_times("app.bsky.notification.getUnreadCount")
+        };
+        assert_eq!(1, checks(&h));
+
+        // Only the timer wakes the view without any input
+        while checks(&h) < 2 {
+            h.wake().await;
+        }
+        assert!(start.elapsed() >= std::time::Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn notifications_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "app.bsky.notification.listNotifications",
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalServerError",
+        );
+        let mut h = Harness::signed_in(client).await;
+        h.key(KeyCode::Char('4'));
+
+        h.wait_until(|h| h.screen().contains("failed to get the notifications"))
+            .await;
+        assert!(!h.client.requested("app.bsky.notification.updateSeen"));
+    }
+}
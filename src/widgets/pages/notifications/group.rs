@@ -0,0 +1,187 @@
+//! Merges the notifications about the same post into one entry.
+
+use atrium_api::app::bsky::notification::list_notifications::Notification;
+#[cfg(test)]
+use rstest::rstest;
+
+/// Notifications of the same reason on the same subject, the newest first.
+#[derive(Debug)]
+pub struct Group {
+    pub items: Vec<Notification>,
+    /// Lists every author instead of the summary.
+    pub expanded: bool,
+}
+
+impl Group {
+    /// The newest notification of the group.
+    pub fn first(&self) -> &Notification {
+        &self.items[0]
+    }
+
+    pub fn is_read(&self) -> bool {
+        self.items.iter().all(|item| item.is_read)
+    }
+
+    /// Summarizes the authors like "Alice, Bob and 12 others".
+    pub fn names(&self) -> String {
+        summarize(&self.items.iter().map(name).collect::<Vec<_>>())
+    }
+}
+
+/// The display name of the author, or the handle if it is not set.
+pub fn name(notification: &Notification) -> &str {
+    match &notification.author.display_name {
+        Some(name) if !name.is_empty() => name,
+        _ => notification.author.handle.as_str(),
+    }
+}
+
+/// Returns what the notifications can be grouped by, or `None` if they must be kept separate.
+fn key(notification: &Notification) -> Option<(&str, &str)> {
+    match notification.reason.as_str() {
+        // Replies, mentions and quotes are posts to read one by one
+        reason @ ("like" | "repost") => Some((reason, notification.reason_subject.as_deref()?)),
+        _ => None,
+    }
+}
+
+/// Adds `notifications` to `groups`, merging them into the groups of the same key.
+/// The groups stay in the order of their newest notifications.
+pub fn group(groups: &mut Vec<Group>, notifications: impl IntoIterator<Item = Notification>) {
+    for notification in notifications {
+        let existing = key(&notification).and_then(|key| {
+            groups
+                .iter()
+                .position(|group| self::key(group.first()) == Some(key))
+        });
+        match existing {
+            Some(i) => groups[i].items.push(notification),
+            None => groups.push(Group {
+                items: vec![notification],
+                expanded: false,
+            }),
+        }
+    }
+}
+
+fn summarize(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.to_string(),
+        [first, second] => format!("{first} and {second}"),
+        [first, second, third] => format!("{first}, {second} and {third}"),
+        [first, second, rest @ ..] => format!("{first}, {second} and {} others", rest.len()),
+    }
+}
+
+#[cfg(test)]
+#[rstest]
+#[case("", &[])]
+#[case("Alice", &["Alice"])]
+#[case("Alice and Bob", &["Alice", "Bob"])]
+#[case("Alice, Bob and Carol", &["Alice", "Bob", "Carol"])]
+#[case("Alice, Bob and 2 others", &["Alice", "Bob", "Carol", "Dave"])]
+#[case("Alice, Bob and 12 others", &["Alice", "Bob", "x", "x", "x", "x", "x", "x", "x", "x", "x", "x", "x", "x"])]
+fn test_summarize(#[case] expected: &str, #[case] names: &[&str]) {
+    assert_eq!(expected, summarize(names));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(author: &str, reason: &str, subject: Option<&str>) -> Notification {
+        let subject = subject.map(|rkey| format!("at://did:plc:alice/app.bsky.feed.post/{rkey}"));
+        serde_json::from_value(serde_json::json!({
+            "uri": format!("at://did:plc:{author}/app.bsky.feed.{reason}/3kwapx{author}"),
+            "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+            "author": {
+                "did": format!("did:plc:{author}"),
+                "handle": format!("{author}.test"),
+            },
+            "reason": reason,
+            "reasonSubject": subject,
+            "record": {
+                "$type": "app.bsky.feed.post",
+                "text": "",
+                "createdAt": "2024-07-01T12:00:00.000Z"
+            },
+            "isRead": author != "carol",
+            "indexedAt": "2024-07-01T12:00:00.000Z"
+        }))
+        .unwrap()
+    }
+
+    /// Summarizes the groups by the handles of the authors.
+    fn summary(groups: &[Group]) -> Vec<(String, &str)> {
+        groups
+            .iter()
+            .map(|group| (group.names(), group.first().reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn merge_same_subject() {
+        let mut groups = Vec::new();
+        group(
+            &mut groups,
+            [
+                notification("bob", "like", Some("a")),
+                notification("carol", "repost", Some("a")),
+                notification("dave", "like", Some("a")),
+                notification("erin", "like", Some("b")),
+                notification("frank", "like", Some("a")),
+            ],
+        );
+        assert_eq!(
+            vec![
+                (String::from("bob.test, dave.test and frank.test"), "like"),
+                (String::from("carol.test"), "repost"),
+                (String::from("erin.test"), "like"),
+            ],
+            summary(&groups)
+        );
+        assert!(groups[0].is_read());
+        assert!(!groups[1].is_read());
+    }
+
+    #[test]
+    fn keep_posts_separate() {
+        let mut groups = Vec::new();
+        group(
+            &mut groups,
+            [
+                notification("bob", "reply", Some("a")),
+                notification("dave", "reply", Some("a")),
+                notification("bob", "mention", None),
+                notification("dave", "follow", None),
+                notification("erin", "follow", None),
+                notification("frank", "like", None),
+                notification("grace", "like", None),
+            ],
+        );
+        assert_eq!(7, groups.len());
+    }
+
+    #[test]
+    fn merge_across_pages() {
+        let mut groups = Vec::new();
+        group(&mut groups, [notification("bob", "like", Some("a"))]);
+        groups[0].expanded = true;
+        group(
+            &mut groups,
+            [
+                notification("dave", "follow", None),
+                notification("erin", "like", Some("a")),
+            ],
+        );
+        assert_eq!(
+            vec![
+                (String::from("bob.test and erin.test"), "like"),
+                (String::from("dave.test"), "follow"),
+            ],
+            summary(&groups)
+        );
+        assert!(groups[0].expanded);
+    }
+}
@@ -0,0 +1,448 @@
+use atrium_api::app::bsky::actor::defs::{ProfileViewDetailed, ViewerState};
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    atp::{ActorList, FeedSource, Response},
+    prelude::*,
+    widgets::{
+        atoms::{BlockExt, Spinner, Text},
+        organisms::Feed,
+        Reaction, Store, Storeable, ViewID,
+    },
+};
+
+/// Shows who an actor is and the posts of the actor.
+#[derive(Debug, Default)]
+pub struct Profile {
+    actor: String,
+    response: Response<crate::atp::GetProfileResult>,
+    profile: Option<ProfileViewDetailed>,
+    /// Whether the account follows the actor, with the count of the followers.
+    follow: Option<Reaction>,
+    feed: Feed,
+}
+
+impl Profile {
+    fn get_profile(&mut self, app: &App) {
+        self.response = app.atp().get_profile(self.actor.clone());
+    }
+
+    fn toggle_follow(&mut self, app: &App) {
+        let (Some(profile), Some(follow)) = (&self.profile, &mut self.follow) else {
+            return;
+        };
+        // Nobody can follow themselves
+        if Some(&profile.did) == app.atp().account().did.as_ref() {
+            return;
+        }
+        let did = profile.did.clone();
+        follow.toggle(|undo| app.atp().follow(did, undo));
+    }
+}
+
+impl WidgetRef for Profile {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        let Some(profile) = &self.profile else {
+            if self.response.is_loading() {
+                Spinner::new().render_ref(area, buf);
+            }
+            return;
+        };
+
+        let mut store = Store::new();
+        Block::new()
+            .borders(Borders::BOTTOM)
+            .border_style(Style::new().blue().dim())
+            .wrap(move |inner, s| store_header(profile, self.follow.as_ref(), inner, s))
+            .fit_vertical()
+            .store(area, &mut store);
+        let header_height = store.stored_area().height;
+        store.render_ref(area, buf);
+
+        let feed_area = Rect {
+            y: area.y + header_height,
+            height: area.height.saturating_sub(header_height),
+            ..area
+        };
+        self.feed.render_ref(feed_area, buf);
+    }
+}
+
+fn store_header<'a>(
+    profile: &'a ProfileViewDetailed,
+    follow: Option<&Reaction>,
+    area: Rect,
+    store: &mut Store<'a>,
+) {
+    Text::from_iter({
+        let mut spans = Vec::new();
+        if let Some(name) = profile
+            .display_name
+            .as_ref()
+            .filter(|name| !name.is_empty())
+        {
+            spans.extend([name.clone().bold(), "  ".into()]);
+        }
+        spans.push(format!("@{}", profile.handle.as_str()).dim());
+        spans
+    })
+    .store(store.bottom_space(area).height(1), store);
+
+    let following = follow.is_some_and(|follow| follow.active);
+    let labels = relationship(profile.viewer.as_ref(), following);
+    if !labels.is_empty() {
+        Text::from_iter(labels.into_iter().flat_map(|label| [label, " ".into()]))
+            .store(store.bottom_space(area).height(1), store);
+    }
+
+    if let Some(description) = &profile.description {
+        Block::new()
+            .padding(Padding::top(1))
+            .wrap(move |inner, s| {
+                for line in description.lines() {
+                    Text::from(line.to_string())
+                        .ignore_if_empty(false)
+                        .store(s.bottom_space(inner), s);
+                }
+            })
+            .fit_vertical()
+            .store(store.bottom_space(area), store);
+    }
+
+    let count = |n: Option<i64>, unit: &str| {
+        [
+            n.unwrap_or_default().max(0).to_string().bold(),
+            format!(" {unit}   ").dim(),
+        ]
+    };
+    Block::new()
+        .padding(Padding::vertical(1))
+        .wrap_child(Text::from_iter(
+            [
+                count(profile.posts_count, "posts"),
+                count(profile.follows_count, "following"),
+                count(
+                    follow.map_or(profile.followers_count, |follow| Some(follow.count as i64)),
+                    "followers",
+                ),
+            ]
+            .into_iter()
+            .flatten(),
+        ))
+        .fit_vertical()
+        .store(store.bottom_space(area), store);
+
+    Text::from(" f: follow    F: followers    G: following".dim())
+        .store(store.bottom_space(area).height(1), store);
+}
+
+/// Describes how the account and the actor are related to each other.
+fn relationship(viewer: Option<&ViewerState>, following: bool) -> Vec<Span<'static>> {
+    let mut labels = Vec::new();
+    if following {
+        labels.push(" Following ".black().on_blue());
+    }
+    let Some(viewer) = viewer else {
+        return labels;
+    };
+    if viewer.followed_by.is_some() {
+        labels.push(" Follows you ".black().on_gray());
+    }
+    if viewer.muted == Some(true) {
+        labels.push(" Muted ".black().on_yellow());
+    }
+    if viewer.blocking.is_some() {
+        labels.push(" Blocked ".black().on_red());
+    }
+    if viewer.blocked_by == Some(true) {
+        labels.push(" Blocks you ".black().on_red());
+    }
+    labels
+}
+
+impl crate::app::EventHandler for Profile {
+    fn on_render(&mut self, app: &mut App) {
+        if let ViewID::Profile { actor } = app.view_id() {
+            if actor != &self.actor {
+                *self = Self {
+                    actor: actor.clone(),
+                    feed: Feed::new(FeedSource::Author(actor.clone())),
+                    ..Default::default()
+                };
+                self.get_profile(app);
+            }
+        }
+        match self.response.take_data() {
+            Some(Ok(profile)) => {
+                self.follow = Some(Reaction::new(
+                    profile.followers_count.unwrap_or(0).max(0) as u64,
+                    profile
+                        .viewer
+                        .as_ref()
+                        .and_then(|viewer| viewer.following.clone()),
+                ));
+                self.profile = Some(profile);
+            }
+            Some(Err(err)) => app.report_error(err.context("failed to get the profile")),
+            None => {}
+        }
+        if let Some(err) = self.follow.as_mut().and_then(Reaction::take_error) {
+            app.report_error(err.context("failed to update the follow"));
+        }
+        // The posts are fetched once there is space for them below the profile
+        if self.profile.is_some() {
+            self.feed.on_render(app);
+        }
+    }
+
+    fn on_retry(&mut self, app: &mut App) {
+        if self.profile.is_none() {
+            self.get_profile(app);
+        }
+        self.feed.on_retry(app);
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        if ev.code == KeyCode::Esc && !self.feed.is_confirming() {
+            app.set_view_id(ViewID::Home);
+            return;
+        }
+        if !self.feed.is_confirming() {
+            let list = match ev.code {
+                KeyCode::Char('f') => {
+                    self.toggle_follow(app);
+                    return;
+                }
+                KeyCode::Char('F') => ActorList::Followers,
+                KeyCode::Char('G') => ActorList::Follows,
+                _ => return self.feed.on_key(ev, app),
+            };
+            app.set_view_id(ViewID::Actors {
+                actor: self.actor.clone(),
+                list,
+            });
+            return;
+        }
+        self.feed.on_key(ev, app);
+    }
+
+    fn is_animating(&self) -> bool {
+        self.response.is_loading() || self.feed.is_animating()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::xrpc::http::StatusCode;
+
+    use super::*;
+    use crate::{
+        app::{testing::Harness, EventHandler},
+        atp::fixture::FixtureClient,
+    };
+
+    #[tokio::test]
+    async fn open_from_home() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+
+        h.wait_until(|h| h.screen().contains("Only on my profile."))
+            .await;
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("did:plc:bob")
+            },
+            h.app.view_id()
+        );
+        let screen = h.screen();
+        assert!(screen.contains("Bob  @bob.test"));
+        assert!(screen.contains("Follows you"));
+        assert!(!screen.contains(" Following "));
+        assert!(screen.contains("Writes fixtures for a living."));
+        assert!(screen.contains("Says hello a lot."));
+        assert!(screen.contains("128 posts   7 following   42 followers"));
+
+        h.key(KeyCode::Esc);
+        h.send(TuiEvent::Tick);
+        assert_eq!(&ViewID::Home, h.app.view_id());
+    }
+
+    #[tokio::test]
+    async fn open_reposter() {
+        let client = FixtureClient::default().with_timeline(|timeline| {
+            timeline["feed"][0]["reason"] = serde_json::json!({
+                "$type": "app.bsky.feed.defs#reasonRepost",
+                "by": { "did": "did:plc:carol", "handle": "carol.test" },
+                "indexedAt": "2024-07-01T13:00:00.000Z",
+            });
+        });
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.screen().contains("Reposted by")).await;
+        h.key(KeyCode::Char('b'));
+
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("did:plc:carol")
+            },
+            h.app.view_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn open_quoted_author() {
+        let client = FixtureClient::default().with_timeline(|timeline| {
+            timeline["feed"][0]["post"]["embed"] = serde_json::json!({
+                "$type": "app.bsky.embed.record#view",
+                "record": {
+                    "$type": "app.bsky.embed.record#viewRecord",
+                    "uri": "at://did:plc:carol/app.bsky.feed.post/3kwaoxquote2a",
+                    "cid": "bafyreie5737gdxlw5i64vzichcalba3z2v5n6icifvx5xytvske7mr3hpm",
+                    "author": { "did": "did:plc:carol", "handle": "carol.test" },
+                    "value": {
+                        "$type": "app.bsky.feed.post",
+                        "text": "Worth quoting.",
+                        "createdAt": "2024-07-01T11:00:00.000Z",
+                    },
+                    "indexedAt": "2024-07-01T11:00:00.000Z",
+                },
+            });
+        });
+        let mut h = Harness::signed_in(client).await;
+        h.wait_until(|h| h.screen().contains("Worth quoting."))
+            .await;
+        // Nobody has reposted the post
+        h.key(KeyCode::Char('b'));
+        assert_eq!(&ViewID::Home, h.app.view_id());
+        h.key(KeyCode::Char('o'));
+
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("did:plc:carol")
+            },
+            h.app.view_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn look_up_handle() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('@'));
+        assert!(h.view.focus_in_textarea());
+        h.type_str("@bob.test");
+        h.key(KeyCode::Enter);
+
+        h.wait_until(|h| h.screen().contains("Only on my profile."))
+            .await;
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("@bob.test")
+            },
+            h.app.view_id()
+        );
+        assert!(h
+            .client
+            .requested("app.bsky.actor.getProfile?actor=bob.test"));
+    }
+
+    #[tokio::test]
+    async fn profile_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "app.bsky.actor.getProfile",
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+        );
+        let mut h = Harness::signed_in(client).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+
+        h.wait_until(|h| h.screen().contains("failed to get the profile"))
+            .await;
+        assert!(!h.screen().contains("Only on my profile."));
+    }
+
+    #[tokio::test]
+    async fn follow() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+        h.wait_until(|h| h.screen().contains("42 followers")).await;
+        h.key(KeyCode::Char('f'));
+        // Updated before the request is done
+        assert!(h.screen().contains("43 followers"));
+        assert!(h.screen().contains(" Following "));
+
+        h.wait_until(|h| h.client.requested("com.atproto.repo.createRecord"))
+            .await;
+        h.send(TuiEvent::Tick);
+        assert_eq!(None, h.app.error());
+        assert!(h.screen().contains("43 followers"));
+    }
+
+    #[tokio::test]
+    async fn unfollow() {
+        let client = FixtureClient::default().patch_xrpc("app.bsky.actor.getProfile", |profile| {
+            profile["viewer"]["following"] =
+                "at://did:plc:alice/app.bsky.graph.follow/3kwapzfollow2a".into();
+        });
+        let mut h = Harness::signed_in(client).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+        h.wait_until(|h| h.screen().contains(" Following ")).await;
+        h.key(KeyCode::Char('f'));
+        assert!(h.screen().contains("41 followers"));
+        assert!(!h.screen().contains(" Following "));
+
+        h.wait_until(|h| h.client.requested("com.atproto.repo.deleteRecord"))
+            .await;
+        h.send(TuiEvent::Tick);
+        assert_eq!(None, h.app.error());
+        assert!(!h.client.requested("com.atproto.repo.createRecord"));
+    }
+
+    #[tokio::test]
+    async fn follow_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "com.atproto.repo.createRecord",
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+        );
+        let mut h = Harness::signed_in(client).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+        h.wait_until(|h| h.screen().contains("42 followers")).await;
+        h.key(KeyCode::Char('f'));
+
+        // Rolled back
+        h.wait_until(|h| h.screen().contains("failed to update the follow"))
+            .await;
+        assert!(h.screen().contains("42 followers"));
+    }
+
+    #[tokio::test]
+    async fn keep_writing_after_leaving() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('p'));
+        h.wait_until(|h| h.screen().contains("Only on my profile."))
+            .await;
+        h.key(KeyCode::Char('f'));
+        h.key(KeyCode::Char('l'));
+        // Open another profile before the requests are sent
+        h.app.set_view_id(ViewID::Profile {
+            actor: String::from("did:plc:carol"),
+        });
+        h.send(TuiEvent::Tick);
+
+        h.wait_until(|h| h.client.requested_times("com.atproto.repo.createRecord") == 2)
+            .await;
+    }
+}
@@ -0,0 +1,334 @@
+use ratatui::{prelude::*, widgets::*};
+use tui_textarea::{Input, Key};
+
+use crate::{
+    atp::{ActorSource, FeedSource, SearchSort},
+    prelude::*,
+    widgets::{
+        atoms::TextArea,
+        organisms::{Feed, ProfileList},
+        ViewID,
+    },
+};
+
+/// Which results are shown below the query.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Section {
+    #[default]
+    Posts,
+    Accounts,
+}
+
+/// Finds the posts and the accounts matching a query.
+#[derive(Debug)]
+pub struct Search {
+    textarea: TextArea<'static>,
+    focus: bool,
+    /// The query of the results, set once the user submits it
+    query: Option<String>,
+    section: Section,
+    sort: SearchSort,
+    posts: Feed,
+    accounts: ProfileList,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        let mut search = Self {
+            textarea: TextArea::new(" Search ", false),
+            focus: false,
+            query: None,
+            section: Section::default(),
+            sort: SearchSort::default(),
+            posts: Feed::default(),
+            accounts: ProfileList::default(),
+        };
+        search.set_focus();
+        search
+    }
+}
+
+impl Search {
+    /// Searches again from the first page with the query in the textarea.
+    fn submit(&mut self) {
+        let query = self.textarea.lines().join(" ").trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        self.posts = Feed::new(FeedSource::Search {
+            query: query.clone(),
+            sort: self.sort,
+        });
+        self.accounts = ProfileList::new(ActorSource::Search(query.clone()));
+        self.query = Some(query);
+        self.lose_focus();
+    }
+
+    fn toggle_sort(&mut self) {
+        self.sort = match self.sort {
+            SearchSort::Top => SearchSort::Latest,
+            SearchSort::Latest => SearchSort::Top,
+        };
+        if let Some(query) = &self.query {
+            self.posts = Feed::new(FeedSource::Search {
+                query: query.clone(),
+                sort: self.sort,
+            });
+        }
+    }
+
+    fn set_focus(&mut self) {
+        self.focus = true;
+        self.textarea.set_focus();
+    }
+
+    fn lose_focus(&mut self) {
+        self.focus = false;
+        self.textarea.lose_focus();
+    }
+}
+
+impl WidgetRef for Search {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [textarea_area, tabs_area, results_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(2),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        self.textarea.widget().render(textarea_area, buf);
+
+        let tab = |section: Section, title: &'static str| {
+            if section == self.section {
+                title.blue().bold()
+            } else {
+                title.dim()
+            }
+        };
+        Line::from_iter([
+            tab(Section::Posts, " Posts"),
+            " · ".dim(),
+            tab(Section::Accounts, "Accounts"),
+        ])
+        .render(tabs_area, buf);
+        let help = match self.section {
+            Section::Posts => format!("s: {} first    Tab: accounts ", self.sort.as_str()),
+            Section::Accounts => String::from("Tab: posts "),
+        };
+        Line::from(help.dim())
+            .right_aligned()
+            .render(tabs_area, buf);
+
+        if self.query.is_none() {
+            return;
+        }
+        match self.section {
+            Section::Posts => self.posts.render_ref(results_area, buf),
+            Section::Accounts => self.accounts.render_ref(results_area, buf),
+        }
+    }
+}
+
+impl crate::app::EventHandler for Search {
+    fn on_render(&mut self, app: &mut App) {
+        if self.query.is_none() {
+            return;
+        }
+        // The hidden results are fetched once the user switches to them
+        match self.section {
+            Section::Posts => self.posts.on_render(app),
+            Section::Accounts => self.accounts.on_render(app),
+        }
+    }
+
+    fn on_retry(&mut self, app: &mut App) {
+        self.posts.on_retry(app);
+        self.accounts.on_retry(app);
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        if self.section == Section::Posts && self.posts.is_confirming() {
+            self.posts.on_key(ev, app);
+            return;
+        }
+        match ev.code {
+            KeyCode::Esc => app.set_view_id(ViewID::Home),
+            KeyCode::Char('/') | KeyCode::Char('i') => self.set_focus(),
+            KeyCode::Tab => {
+                self.section = match self.section {
+                    Section::Posts => Section::Accounts,
+                    Section::Accounts => Section::Posts,
+                };
+            }
+            _ if self.query.is_none() => {}
+            KeyCode::Char('s') if self.section == Section::Posts => self.toggle_sort(),
+            _ => match self.section {
+                Section::Posts => self.posts.on_key(ev, app),
+                Section::Accounts => self.accounts.on_key(ev, app),
+            },
+        }
+    }
+
+    fn on_input(&mut self, input: Input, _app: &mut App) {
+        match input.key {
+            Key::Esc => self.lose_focus(),
+            Key::Enter => self.submit(),
+            _ => {
+                self.textarea.input(input);
+            }
+        }
+    }
+
+    fn focus_in_textarea(&self) -> bool {
+        self.focus
+    }
+    fn is_animating(&self) -> bool {
+        match self.section {
+            Section::Posts => self.posts.is_animating(),
+            Section::Accounts => self.accounts.is_animating(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atrium_api::xrpc::http::StatusCode;
+
+    use super::*;
+    use crate::{
+        app::{testing::Harness, EventHandler},
+        atp::fixture::FixtureClient,
+    };
+
+    async fn search(h: &mut Harness, query: &str) {
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Char('5'));
+        h.send(TuiEvent::Tick);
+        assert!(h.view.focus_in_textarea());
+        h.type_str(query);
+        h.key(KeyCode::Enter);
+    }
+
+    #[tokio::test]
+    async fn posts() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        search(&mut h, "fixtures").await;
+
+        h.wait_until(|h| {
+            h.screen()
+                .contains("Fixtures are the best kind of test data.")
+        })
+        .await;
+        assert_eq!(&ViewID::Search, h.app.view_id());
+        assert!(!h.view.focus_in_textarea());
+        assert!(h
+            .client
+            .requested("app.bsky.feed.searchPosts?limit=15&q=fixtures&sort=top"));
+        // The accounts are not searched until they are shown
+        assert!(!h.client.requested("app.bsky.actor.searchActors"));
+
+        h.key(KeyCode::Enter);
+        h.send(TuiEvent::Tick);
+        assert_eq!(
+            &ViewID::Thread {
+                uri: String::from("at://did:plc:carol/app.bsky.feed.post/3kwaoxsearch2a")
+            },
+            h.app.view_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn sort_latest() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        search(&mut h, "fixtures").await;
+        h.wait_until(|h| {
+            h.screen()
+                .contains("Fixtures are the best kind of test data.")
+        })
+        .await;
+        assert!(h.screen().contains("s: top first"));
+
+        h.key(KeyCode::Char('s'));
+        h.wait_until(|h| {
+            h.client
+                .requested("app.bsky.feed.searchPosts?limit=15&q=fixtures&sort=latest")
+        })
+        .await;
+        h.wait_until(|h| {
+            h.screen()
+                .contains("Fixtures are the best kind of test data.")
+        })
+        .await;
+        assert!(h.screen().contains("s: latest first"));
+    }
+
+    #[tokio::test]
+    async fn keep_liking_after_sorting() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        search(&mut h, "fixtures").await;
+        h.wait_until(|h| {
+            h.screen()
+                .contains("Fixtures are the best kind of test data.")
+        })
+        .await;
+        h.key(KeyCode::Char('l'));
+        // Sort again before the like is sent
+        h.key(KeyCode::Char('s'));
+
+        h.wait_until(|h| h.client.requested("com.atproto.repo.createRecord"))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn accounts() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        search(&mut h, "fixtures").await;
+        h.key(KeyCode::Tab);
+
+        h.wait_until(|h| h.screen().contains("Collects fixtures."))
+            .await;
+        assert!(h.screen().contains("Erin  @erin.test"));
+        assert!(h
+            .client
+            .requested("app.bsky.actor.searchActors?limit=15&q=fixtures"));
+
+        h.key(KeyCode::Enter);
+        h.send(TuiEvent::Tick);
+        assert_eq!(
+            &ViewID::Profile {
+                actor: String::from("did:plc:erin")
+            },
+            h.app.view_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn search_error() {
+        let client = FixtureClient::default().xrpc_error(
+            "app.bsky.feed.searchPosts",
+            StatusCode::BAD_REQUEST,
+            "BadQueryString",
+        );
+        let mut h = Harness::signed_in(client).await;
+        search(&mut h, "fixtures").await;
+
+        h.wait_until(|h| h.screen().contains("failed to search the posts"))
+            .await;
+    }
+
+    #[tokio::test]
+    async fn empty_query() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        search(&mut h, "  ").await;
+        h.send(TuiEvent::Tick);
+        assert!(h.view.focus_in_textarea());
+        assert!(!h.client.requested("app.bsky.feed.searchPosts"));
+    }
+}
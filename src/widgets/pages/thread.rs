@@ -0,0 +1,323 @@
+use std::cell::Cell;
+
+use atrium_api::{
+    app::bsky::feed::{
+        defs::{ThreadViewPost, ThreadViewPostParentRefs, ThreadViewPostRepliesItem},
+        get_post_thread::OutputThreadRefs,
+    },
+    types::Union,
+};
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    atp::Response,
+    prelude::*,
+    widgets::{
+        atoms::{BlockExt, Spinner, Text},
+        Post, Store, Storeable, ViewID, SELECTED_BORDER,
+    },
+};
+
+/// Shows a post with its parents above and its replies below.
+#[derive(Debug, Default)]
+pub struct Thread {
+    uri: String,
+    response: Response<crate::atp::GetPostThreadResult>,
+    entries: Vec<Entry>,
+    scroll: u16,
+    /// The height of the thread in the last render, to stop scrolling at the end.
+    height: Cell<u16>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// The level of a reply, or 0 for the post and its parents.
+    depth: usize,
+    node: Node,
+    focused: bool,
+}
+
+#[derive(Debug)]
+enum Node {
+    Post(Box<Post>),
+    NotFound,
+    Blocked,
+    Unknown,
+}
+
+/// Lists the parents from the root, the focused post, and the replies in depth-first order.
+fn flatten(thread: OutputThreadRefs) -> Vec<Entry> {
+    let thread = match thread {
+        OutputThreadRefs::AppBskyFeedDefsThreadViewPost(thread) => thread,
+        OutputThreadRefs::AppBskyFeedDefsNotFoundPost(_) => {
+            return vec![Entry::focused(Node::NotFound)]
+        }
+        OutputThreadRefs::AppBskyFeedDefsBlockedPost(_) => {
+            return vec![Entry::focused(Node::Blocked)]
+        }
+    };
+
+    let ThreadViewPost {
+        mut parent,
+        post,
+        replies,
+    } = *thread;
+
+    let mut entries = Vec::new();
+    while let Some(refs) = parent.take() {
+        let node = match refs {
+            Union::Refs(ThreadViewPostParentRefs::ThreadViewPost(thread)) => {
+                parent = thread.parent;
+                Node::Post(Box::new(thread.post.into()))
+            }
+            Union::Refs(ThreadViewPostParentRefs::NotFoundPost(_)) => Node::NotFound,
+            Union::Refs(ThreadViewPostParentRefs::BlockedPost(_)) => Node::Blocked,
+            Union::Unknown(_) => Node::Unknown,
+        };
+        entries.push(Entry::new(0, node));
+    }
+    entries.reverse();
+
+    entries.push(Entry::focused(Node::Post(Box::new(post.into()))));
+    push_replies(&mut entries, replies, 1);
+    entries
+}
+
+fn push_replies(
+    entries: &mut Vec<Entry>,
+    replies: Option<Vec<Union<ThreadViewPostRepliesItem>>>,
+    depth: usize,
+) {
+    for reply in replies.unwrap_or_default() {
+        match reply {
+            Union::Refs(ThreadViewPostRepliesItem::ThreadViewPost(thread)) => {
+                entries.push(Entry::new(depth, Node::Post(Box::new(thread.post.into()))));
+                push_replies(entries, thread.replies, depth + 1);
+            }
+            Union::Refs(ThreadViewPostRepliesItem::NotFoundPost(_)) => {
+                entries.push(Entry::new(depth, Node::NotFound));
+            }
+            Union::Refs(ThreadViewPostRepliesItem::BlockedPost(_)) => {
+                entries.push(Entry::new(depth, Node::Blocked));
+            }
+            Union::Unknown(_) => entries.push(Entry::new(depth, Node::Unknown)),
+        }
+    }
+}
+
+impl Entry {
+    fn new(depth: usize, node: Node) -> Self {
+        Self {
+            depth,
+            node,
+            focused: false,
+        }
+    }
+
+    fn focused(node: Node) -> Self {
+        Self {
+            focused: true,
+            ..Self::new(0, node)
+        }
+    }
+}
+
+impl Thread {
+    fn get_post_thread(&mut self, app: &App) {
+        self.response = app.atp().get_post_thread(self.uri.clone());
+    }
+
+    fn focused_post(&self) -> Option<&Post> {
+        self.entries
+            .iter()
+            .find(|entry| entry.focused)
+            .and_then(|entry| match &entry.node {
+                Node::Post(post) => Some(post.as_ref()),
+                _ => None,
+            })
+    }
+}
+
+impl WidgetRef for Thread {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(5),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        if self.response.is_loading() {
+            Spinner::new().render_ref(area, buf);
+            return;
+        }
+
+        let mut store = Store::new().scroll_v(self.scroll as i32);
+        for entry in &self.entries {
+            store_indented(
+                entry,
+                entry.depth,
+                store.bottom_space(area.height(u16::MAX)),
+                &mut store,
+            );
+        }
+        self.height.set(store.stored_area().height);
+        store.render_ref(area, buf);
+    }
+}
+
+/// Stores `entry` with an indentation guide for each level of `depth`.
+fn store_indented<'a>(entry: &'a Entry, depth: usize, area: Rect, store: &mut Store<'a>) {
+    if depth > 0 {
+        Block::new()
+            .borders(Borders::LEFT)
+            .border_style(Style::new().dim())
+            .padding(Padding::left(1))
+            .wrap(move |inner, s| store_indented(entry, depth - 1, inner, s))
+            .fit_vertical()
+            .store(area, store);
+        return;
+    }
+    let block = if entry.focused {
+        Block::new()
+            .borders(Borders::LEFT | Borders::BOTTOM)
+            .border_set(SELECTED_BORDER)
+            .border_style(Style::new().blue())
+            .padding(Padding::left(1))
+    } else {
+        Block::new()
+            .borders(Borders::BOTTOM)
+            .border_style(Style::new().blue().dim())
+    };
+    match &entry.node {
+        Node::Post(post) => block.wrap_child(post.as_ref()),
+        Node::NotFound => block.wrap_child(Text::from("  Post not found".dim().italic())),
+        Node::Blocked => block.wrap_child(Text::from("  Blocked post".dim().italic())),
+        Node::Unknown => block.wrap_child(Text::from("unimplemented!")),
+    }
+    .fit_vertical()
+    .store(area, store);
+}
+
+impl crate::app::EventHandler for Thread {
+    fn on_render(&mut self, app: &mut App) {
+        if let ViewID::Thread { uri } = app.view_id() {
+            if uri != &self.uri {
+                *self = Self {
+                    uri: uri.clone(),
+                    ..Default::default()
+                };
+                self.get_post_thread(app);
+            }
+        }
+        match self.response.take_data() {
+            Some(Ok(output)) => {
+                self.entries = match output.thread {
+                    Union::Refs(thread) => flatten(thread),
+                    Union::Unknown(_) => vec![Entry::focused(Node::Unknown)],
+                };
+            }
+            Some(Err(err)) => app.report_error(err.context("failed to get the thread")),
+            None => {}
+        }
+    }
+
+    fn on_retry(&mut self, app: &mut App) {
+        self.get_post_thread(app);
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        match ev.code {
+            KeyCode::Esc => app.set_view_id(ViewID::Home),
+            KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Char('j') => {
+                self.scroll = (self.scroll + 1).min(self.height.get().saturating_sub(1))
+            }
+            KeyCode::Char('r') => {
+                let view = self.focused_post().and_then(Post::view);
+                if let Some(view) = view {
+                    app.set_view_id(ViewID::Compose {
+                        reply_to: Some(Box::new(view.clone())),
+                        quote: None,
+                    });
+                }
+            }
+            KeyCode::Char('p') => {
+                let view = self.focused_post().and_then(Post::view);
+                if let Some(view) = view {
+                    app.set_view_id(ViewID::Profile {
+                        actor: view.author.did.as_str().to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_animating(&self) -> bool {
+        self.response.is_loading()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::testing::Harness, atp::fixture::FixtureClient};
+
+    fn fixture() -> OutputThreadRefs {
+        let output: atrium_api::app::bsky::feed::get_post_thread::Output =
+            serde_json::from_str(include_str!("../../atp/fixtures/get_post_thread.json")).unwrap();
+        match output.thread {
+            Union::Refs(thread) => thread,
+            Union::Unknown(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn flatten_thread() {
+        let entries = flatten(fixture());
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                let node = match &entry.node {
+                    Node::Post(post) => post.view().unwrap().uri.rsplit('/').next().unwrap(),
+                    Node::NotFound => "not found",
+                    Node::Blocked => "blocked",
+                    Node::Unknown => "unknown",
+                };
+                (entry.depth, node, entry.focused)
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (0, "not found", false),
+                (0, "3kwaoqxyz2k2a", false),
+                (0, "3kwaosbrtgk2x", true),
+                (1, "3kwaotreply2a", false),
+                (2, "3kwaotreply2b", false),
+                (1, "blocked", false),
+            ],
+            summary
+        );
+    }
+
+    #[tokio::test]
+    async fn open_from_home() {
+        let mut h = Harness::signed_in(FixtureClient::default()).await;
+        h.wait_for_timeline().await;
+        h.key(KeyCode::Enter);
+
+        h.wait_until(|h| h.screen().contains("Hi, Alice!")).await;
+        assert!(matches!(h.app.view_id(), ViewID::Thread { .. }));
+        let screen = h.screen();
+        assert!(screen.contains("Post not found"));
+        assert!(screen.contains("Say hello to the fixtures."));
+        assert!(screen.contains("Blocked post"));
+        // The reply to the reply is indented twice
+        assert!(screen.contains("│ │ Bob"));
+
+        h.key(KeyCode::Esc);
+        h.send(TuiEvent::Tick);
+        assert_eq!(&ViewID::Home, h.app.view_id());
+    }
+}
@@ -0,0 +1,123 @@
+use crossterm::event::KeyCode;
+use ratatui::{prelude::*, widgets::*};
+use tui_textarea::Key;
+
+use crate::{
+    app::App,
+    atp::Response,
+    widgets::{
+        atoms::{Spinner, TextArea},
+        ViewID,
+    },
+};
+
+/// Asks for the passphrase of the encrypted sessions.
+#[derive(Debug)]
+pub struct Unlock {
+    textarea: TextArea<'static>,
+    focus: bool,
+    unlock_res: Response<anyhow::Result<()>>,
+}
+
+impl Default for Unlock {
+    fn default() -> Self {
+        let mut unlock = Self {
+            textarea: TextArea::new(" Passphrase ", true),
+            focus: false,
+            unlock_res: Response::empty(),
+        };
+        unlock.set_focus();
+        unlock
+    }
+}
+
+impl Unlock {
+    pub fn passphrase(&self) -> String {
+        self.textarea.lines()[0].to_string()
+    }
+
+    fn set_focus(&mut self) {
+        self.focus = true;
+        self.textarea.set_focus();
+    }
+
+    fn lose_focus(&mut self) {
+        self.focus = false;
+        self.textarea.lose_focus();
+    }
+}
+
+impl WidgetRef for Unlock {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let [_, area, _] = Layout::horizontal([
+            Constraint::Percentage(30),
+            Constraint::Min(70),
+            Constraint::Percentage(30),
+        ])
+        .areas(area);
+        let [_, message, passphrase, spinner, _] = Layout::vertical([
+            Constraint::Percentage(30),
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .spacing(1)
+        .areas(area);
+
+        Paragraph::new(vec![
+            Line::from(" Enter the passphrase to decrypt the saved sessions."),
+            Line::from(" It is set on first use.".dim()),
+        ])
+        .render(message, buf);
+        self.textarea.widget().render(passphrase, buf);
+        if self.unlock_res.is_loading() {
+            Spinner::new().render(spinner, buf);
+        }
+    }
+}
+
+impl crate::app::EventHandler for Unlock {
+    fn on_render(&mut self, app: &mut App) {
+        if let Some(result) = self.unlock_res.take_data() {
+            match result {
+                Ok(()) => app.set_view_id(ViewID::default()),
+                Err(err) => {
+                    self.textarea = TextArea::new(" Passphrase ", true);
+                    app.report_error(err.context("failed to unlock the sessions"));
+                }
+            }
+        }
+    }
+
+    fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
+        match ev.code {
+            KeyCode::Esc => app.exit(),
+            KeyCode::Enter | KeyCode::Tab if self.unlock_res.is_empty() => self.set_focus(),
+            _ => {}
+        }
+    }
+
+    fn on_input(&mut self, input: tui_textarea::Input, app: &mut App) {
+        if input.key == Key::Esc {
+            app.exit();
+        } else if input.key == Key::Enter {
+            self.unlock_res = app.unlock(self.passphrase());
+            self.lose_focus();
+        } else {
+            self.textarea.input(input);
+        }
+    }
+
+    fn on_retry(&mut self, _app: &mut App) {
+        self.set_focus();
+    }
+
+    fn focus_in_textarea(&self) -> bool {
+        self.focus
+    }
+
+    fn is_animating(&self) -> bool {
+        self.unlock_res.is_loading()
+    }
+}
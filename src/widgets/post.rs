@@ -1,5 +1,13 @@
+mod richtext;
+
+use std::ops::Range;
+
 use atrium_api::{
-    app::bsky::{self, feed::defs::FeedViewPost},
+    app::bsky::{
+        self,
+        feed::defs::{FeedViewPost, PostView},
+    },
+    com::atproto::repo::strong_ref,
     records,
     types::Union,
 };
@@ -9,19 +17,27 @@ use crate::{
     prelude::*,
     widgets::{
         atoms::{BlockExt, Text},
-        Store, Storeable,
+        Reaction, Store, Storeable,
     },
 };
 
+pub use self::richtext::content;
+
 #[derive(Debug, Default)]
 pub struct Posts {
     posts: Vec<Post>,
     pub scroll: u16,
+    /// The index of the post to interact with.
+    selected: usize,
 }
 
 #[derive(Debug, Default)]
 pub struct PostsState {
     pub blank_height: Option<u16>,
+    /// The height of the viewport in the last render.
+    height: u16,
+    /// The vertical ranges of the posts in the last render, without scrolling.
+    post_ranges: Vec<Range<u16>>,
 }
 
 impl Posts {
@@ -33,6 +49,92 @@ impl Posts {
             self.posts.push(post);
         }
     }
+
+    pub fn toggle_like(&mut self, atp: &Atp) {
+        let Some(post) = self.posts.get_mut(self.selected) else {
+            return;
+        };
+        if let Some(subject) = post.strong_ref() {
+            post.likes.toggle(|undo| atp.like(subject, undo));
+        }
+    }
+
+    pub fn toggle_repost(&mut self, atp: &Atp) {
+        let Some(post) = self.posts.get_mut(self.selected) else {
+            return;
+        };
+        if let Some(subject) = post.strong_ref() {
+            post.reposts.toggle(|undo| atp.repost(subject, undo));
+        }
+    }
+
+    /// Applies the results of likes and reposts, reporting the failed ones.
+    pub fn apply_reactions(&mut self, app: &mut App) {
+        for post in &mut self.posts {
+            if let Some(err) = post.likes.take_error() {
+                app.report_error(err.context("failed to update the like"));
+            }
+            if let Some(err) = post.reposts.take_error() {
+                app.report_error(err.context("failed to update the repost"));
+            }
+        }
+    }
+
+    /// Removes the posts at `uri`, including their reposts, keeping the selection on the same
+    /// position.
+    pub fn remove_post(&mut self, uri: &str) {
+        let removed_before = self.posts[..self.selected.min(self.posts.len())]
+            .iter()
+            .filter(|post| post.uri() == Some(uri))
+            .count();
+        self.posts.retain(|post| post.uri() != Some(uri));
+        self.selected -= removed_before;
+        self.selected = self.selected.min(self.posts.len().saturating_sub(1));
+    }
+
+    /// Applies `f` to the posts at `uri`, including their reposts.
+    pub fn update_post(&mut self, uri: &str, mut f: impl FnMut(&mut Post)) {
+        self.posts
+            .iter_mut()
+            .filter(|post| post.uri() == Some(uri))
+            .for_each(&mut f);
+    }
+
+    pub fn selected(&self) -> Option<&PostView> {
+        self.posts.get(self.selected)?.view()
+    }
+
+    pub fn selected_post(&self) -> Option<&Post> {
+        self.posts.get(self.selected)
+    }
+
+    pub fn select_prev(&mut self, state: &PostsState) {
+        self.select(self.selected.saturating_sub(1), state);
+    }
+
+    pub fn select_next(&mut self, state: &PostsState) {
+        self.select(self.selected + 1, state);
+    }
+
+    /// Selects the post at `index`, scrolling to show it as much as possible.
+    fn select(&mut self, index: usize, state: &PostsState) {
+        self.selected = index.min(self.posts.len().saturating_sub(1));
+        if let Some(range) = state.post_ranges.get(self.selected) {
+            self.scroll = scroll_to(self.scroll, range, state.height);
+        }
+    }
+}
+
+/// The scroll position showing the rows in `range` as much as possible in a viewport of
+/// `height`, moving from `scroll` as little as possible.
+pub fn scroll_to(scroll: u16, range: &Range<u16>, height: u16) -> u16 {
+    if range.start < scroll || range.len() > height as usize {
+        range.start
+    } else if range.end > scroll + height {
+        range.end - height
+    } else {
+        scroll
+    }
 }
 
 impl StatefulWidgetRef for Posts {
@@ -40,30 +142,60 @@ impl StatefulWidgetRef for Posts {
 
     fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let mut store = Store::new().scroll_v(self.scroll as i32);
-        for post in &self.posts {
+        state.height = area.height;
+        state.post_ranges.clear();
+        for (i, post) in self.posts.iter().enumerate() {
+            let space = store.bottom_space(area.height(u16::MAX));
+            let (border_set, border_style) = if i == self.selected {
+                (SELECTED_BORDER, Style::new().blue())
+            } else {
+                (BORDER, Style::new().blue().dim())
+            };
             Block::new()
-                .borders(Borders::BOTTOM)
-                .border_style(Style::new().blue().dim())
+                .borders(Borders::LEFT | Borders::BOTTOM)
+                .border_set(border_set)
+                .border_style(border_style)
+                .padding(Padding::left(1))
                 .wrap_child(post)
                 .fit_vertical()
-                .store(store.bottom_space(area.height(u16::MAX)), &mut store);
+                .store(space, &mut store);
+            state
+                .post_ranges
+                .push(space.y - area.y..store.stored_area().bottom() - area.y);
         }
         state.blank_height = (self.scroll + area.height).checked_sub(store.stored_area().height);
         store.render_ref(area, buf);
     }
 }
 
+pub const BORDER: symbols::border::Set = symbols::border::Set {
+    vertical_left: " ",
+    ..symbols::border::PLAIN
+};
+
+/// Marks the selected post with a thick line on the left.
+pub const SELECTED_BORDER: symbols::border::Set = symbols::border::Set {
+    vertical_left: symbols::line::THICK_VERTICAL,
+    bottom_left: "┖",
+    ..symbols::border::PLAIN
+};
+
 nestify::nest! {
     #[derive(Debug)]*
-    struct Post {
+    pub struct Post {
+        /// The source of a post in a feed, used to interact with it.
+        view: Option<Box<PostView>>,
+        /// Waiting for the post to be deleted.
+        deleting: bool,
         author: struct Account {
+            did: String,
             name: String,
             opt_name: Option<String>,
         },
         content: Text,
-        likes: u64,
+        likes: Reaction,
         replies: u64,
-        reposts: u64,
+        reposts: Reaction,
         reposted_by: Option<Account>,
         embed: Option<enum Embed {
             Media(enum EmbedMedia {
@@ -76,7 +208,7 @@ nestify::nest! {
                     alt: Text,
                 }>),
             }),
-            Record(enum EmbedRecord {
+            Record(pub enum EmbedRecord {
                 NotFound,
                 Blocked,
                 Post(Box<Post>),
@@ -88,30 +220,85 @@ nestify::nest! {
     }
 }
 
+impl Post {
+    pub fn view(&self) -> Option<&PostView> {
+        self.view.as_deref()
+    }
+
+    pub fn uri(&self) -> Option<&str> {
+        self.view().map(|view| view.uri.as_str())
+    }
+
+    pub fn set_deleting(&mut self, deleting: bool) {
+        self.deleting = deleting;
+    }
+
+    /// The DID of the account that has reposted the post into the feed.
+    pub fn reposted_by(&self) -> Option<&str> {
+        Some(self.reposted_by.as_ref()?.did.as_str())
+    }
+
+    /// The DID of the author of the quoted post.
+    pub fn quoted_author(&self) -> Option<&str> {
+        match &self.embed {
+            Some(
+                Embed::Record(EmbedRecord::Post(post))
+                | Embed::RecordWithMedia(EmbedRecord::Post(post), _),
+            ) => Some(post.author.did.as_str()),
+            _ => None,
+        }
+    }
+
+    fn strong_ref(&self) -> Option<strong_ref::Main> {
+        let view = self.view.as_ref()?;
+        Some(strong_ref::Main {
+            cid: view.cid.clone(),
+            uri: view.uri.clone(),
+        })
+    }
+}
+
 impl From<FeedViewPost> for Post {
     fn from(value: FeedViewPost) -> Self {
-        let post = &value.post;
+        Self {
+            reposted_by: match value.reason {
+                Some(Union::Refs(bsky::feed::defs::FeedViewPostReasonRefs::ReasonRepost(
+                    repost,
+                ))) => Some(repost.by.into()),
+                _ => None,
+            },
+            ..value.post.into()
+        }
+    }
+}
+
+impl From<PostView> for Post {
+    fn from(value: PostView) -> Self {
+        let post = &value;
         Self {
             author: post.author.clone().into(),
             content: match &post.record {
                 records::Record::Known(records::KnownRecord::AppBskyFeedPost(record)) => {
-                    record.text.clone().into()
+                    richtext::content(record)
                 }
                 _ => "unimplemented!".into(),
             },
-            likes: post.like_count.unwrap_or(0) as u64,
+            likes: Reaction::new(
+                post.like_count.unwrap_or(0) as u64,
+                post.viewer.as_ref().and_then(|v| v.like.clone()),
+            ),
             replies: post.reply_count.unwrap_or(0) as u64,
-            reposts: post.repost_count.unwrap_or(0) as u64,
-            reposted_by: match value.reason {
-                Some(Union::Refs(bsky::feed::defs::FeedViewPostReasonRefs::ReasonRepost(
-                    repost,
-                ))) => Some(repost.by.into()),
-                _ => None,
-            },
+            reposts: Reaction::new(
+                post.repost_count.unwrap_or(0) as u64,
+                post.viewer.as_ref().and_then(|v| v.repost.clone()),
+            ),
+            reposted_by: None,
             embed: match post.embed.clone() {
                 Some(Union::Refs(embed)) => Some(embed.into()),
                 _ => None,
             },
+            view: Some(Box::new(value)),
+            deleting: false,
         }
     }
 }
@@ -119,16 +306,18 @@ impl From<FeedViewPost> for Post {
 impl From<Box<bsky::embed::record::ViewRecord>> for Post {
     fn from(value: Box<bsky::embed::record::ViewRecord>) -> Self {
         Self {
+            view: None,
+            deleting: false,
             author: value.author.into(),
             content: match &value.value {
                 records::Record::Known(records::KnownRecord::AppBskyFeedPost(record)) => {
-                    record.text.clone().into()
+                    richtext::content(record)
                 }
                 _ => "unimplemented!".into(),
             },
-            likes: value.like_count.unwrap_or(0) as u64,
+            likes: Reaction::new(value.like_count.unwrap_or(0) as u64, None),
             replies: value.reply_count.unwrap_or(0) as u64,
-            reposts: value.repost_count.unwrap_or(0) as u64,
+            reposts: Reaction::new(value.repost_count.unwrap_or(0) as u64, None),
             reposted_by: None,
             embed: value
                 .embeds
@@ -145,13 +334,16 @@ impl From<Box<bsky::embed::record::ViewRecord>> for Post {
 
 impl From<bsky::actor::defs::ProfileViewBasic> for Account {
     fn from(value: bsky::actor::defs::ProfileViewBasic) -> Self {
+        let did = value.did.as_str().to_string();
         let handle = format!("@{}", value.handle.as_str());
         match value.display_name {
             Some(display_name) => Self {
+                did,
                 name: display_name,
                 opt_name: Some(handle),
             },
             None => Self {
+                did,
                 name: handle,
                 opt_name: None,
             },
@@ -212,6 +404,12 @@ impl From<Box<bsky::embed::record_with_media::View>> for Embed {
     }
 }
 
+impl From<PostView> for EmbedRecord {
+    fn from(value: PostView) -> Self {
+        Self::Post(Box::new(value.into()))
+    }
+}
+
 impl From<bsky::embed::record::ViewRecordRefs> for EmbedRecord {
     fn from(value: bsky::embed::record::ViewRecordRefs) -> Self {
         use bsky::embed::record::ViewRecordRefs::*;
@@ -266,6 +464,10 @@ impl From<bsky::embed::images::ViewImage> for EmbedImage {
 
 impl<'a> Storeable<'a> for &'a Post {
     fn store(self, area: Rect, store: &mut Store<'a>) {
+        if self.deleting {
+            Text::from("  Deleting…".dim().italic())
+                .store(store.bottom_space(area).height(1), store);
+        }
         if let Some(reposted_by) = &self.reposted_by {
             Text::from(format!("  Reposted by {}", reposted_by.name))
                 .store(store.bottom_space(area).height(1), store);
@@ -287,10 +489,23 @@ impl<'a> Storeable<'a> for &'a Post {
         }
         Block::new()
             .padding(Padding::top(1))
-            .wrap_child(Text::from(format!(
-                " {}    {}   ♥ {}",
-                self.replies, self.reposts, self.likes
-            )))
+            .wrap_child(Text::from_iter([
+                format!(" {}   ", self.replies).into(),
+                {
+                    let reposts = format!(" {}", self.reposts.count);
+                    if self.reposts.active {
+                        reposts.green()
+                    } else {
+                        reposts.into()
+                    }
+                },
+                "   ".into(),
+                if self.likes.active {
+                    format!("♥ {}", self.likes.count).red()
+                } else {
+                    format!("♡ {}", self.likes.count).into()
+                },
+            ]))
             .fit_vertical()
             .store(store.bottom_space(area), store);
     }
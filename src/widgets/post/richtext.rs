@@ -0,0 +1,215 @@
+//! Styles the mentions, links and hashtags in the text of a post.
+
+use std::ops::Range;
+
+use atrium_api::{
+    app::bsky::{feed::post, richtext::facet},
+    types::Union,
+};
+use ratatui::prelude::*;
+#[cfg(test)]
+use rstest::rstest;
+
+use crate::widgets::atoms::Text;
+
+/// Builds the content of a post from its text and facets.
+pub fn content(record: &post::Record) -> Text {
+    let facets = record.facets.as_deref().unwrap_or_default();
+    Text::from_iter(
+        segments(&record.text, facets)
+            .into_iter()
+            .flat_map(|(s, feature)| spans(s, feature)),
+    )
+}
+
+fn spans(s: &str, feature: Option<&facet::MainFeaturesItem>) -> Vec<Span<'static>> {
+    match feature {
+        None => vec![s.to_string().into()],
+        Some(facet::MainFeaturesItem::Mention(_)) => vec![s.to_string().light_blue()],
+        Some(facet::MainFeaturesItem::Tag(_)) => vec![s.to_string().cyan()],
+        Some(facet::MainFeaturesItem::Link(link)) => {
+            let mut spans = vec![s.to_string().blue().underlined()];
+            // Show where a shortened link goes
+            if is_shortened(s, &link.uri) {
+                spans.push(format!(" ({})", link.uri).dim());
+            }
+            spans
+        }
+    }
+}
+
+/// Splits `text` into the plain parts and the parts annotated by `facets`.
+/// Facets with invalid or overlapping byte slices are ignored.
+fn segments<'a>(
+    text: &'a str,
+    facets: &'a [facet::Main],
+) -> Vec<(&'a str, Option<&'a facet::MainFeaturesItem>)> {
+    let mut ranges: Vec<_> = facets
+        .iter()
+        .filter_map(|facet| {
+            let range = byte_range(text, &facet.index)?;
+            let feature = facet.features.iter().find_map(|f| match f {
+                Union::Refs(feature) => Some(feature),
+                Union::Unknown(_) => None,
+            })?;
+            Some((range, feature))
+        })
+        .collect();
+    ranges.sort_by_key(|(range, _)| range.start);
+
+    let mut segments = Vec::new();
+    let mut pos = 0;
+    for (range, feature) in ranges {
+        if range.start < pos {
+            continue;
+        }
+        if pos < range.start {
+            segments.push((&text[pos..range.start], None));
+        }
+        segments.push((&text[range.clone()], Some(feature)));
+        pos = range.end;
+    }
+    if pos < text.len() {
+        segments.push((&text[pos..], None));
+    }
+    segments
+}
+
+/// Maps the byte slice of a facet to a range of `text`,
+/// or `None` if it is empty, out of bounds, or splits a character.
+fn byte_range(text: &str, index: &facet::ByteSlice) -> Option<Range<usize>> {
+    let range = index.byte_start..index.byte_end;
+    (!range.is_empty() && text.get(range.clone()).is_some()).then_some(range)
+}
+
+/// Clients shorten long links in the text, keeping the full URI in the facet.
+fn is_shortened(text: &str, uri: &str) -> bool {
+    let without_scheme = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+    text != uri && text != without_scheme
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(Some(0..5), "hello", 0, 5)]
+#[case(Some(6..15), "hello こんにちは", 6, 15)]
+#[case(Some(6..21), "hello こんにちは", 6, 21)]
+#[case(None, "hello こんにちは", 6, 22)]
+#[case(None, "hello こんにちは", 7, 15)]
+#[case(None, "hello こんにちは", 6, 14)]
+#[case(Some(0..4), "🦀 #rust", 0, 4)]
+#[case(None, "🦀 #rust", 0, 2)]
+#[case(None, "👨‍👩‍👧", 4, 5)]
+#[case(Some(4..7), "👨‍👩‍👧", 4, 7)]
+#[case(None, "hello", 3, 3)]
+#[case(None, "hello", 4, 2)]
+fn test_byte_range(
+    #[case] expected: Option<Range<usize>>,
+    #[case] text: &str,
+    #[case] byte_start: usize,
+    #[case] byte_end: usize,
+) {
+    assert_eq!(
+        expected,
+        byte_range(
+            text,
+            &facet::ByteSlice {
+                byte_start,
+                byte_end
+            }
+        )
+    );
+}
+
+#[cfg(test)]
+#[rstest]
+#[case(false, "https://example.com", "https://example.com")]
+#[case(false, "example.com", "https://example.com")]
+#[case(true, "example.com/very/lo...", "https://example.com/very/long/path")]
+fn test_is_shortened(#[case] expected: bool, #[case] text: &str, #[case] uri: &str) {
+    assert_eq!(expected, is_shortened(text, uri));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facet(byte_start: usize, byte_end: usize, tag: &str) -> facet::Main {
+        facet::Main {
+            features: vec![Union::Refs(facet::MainFeaturesItem::Tag(Box::new(
+                facet::Tag {
+                    tag: tag.to_string(),
+                },
+            )))],
+            index: facet::ByteSlice {
+                byte_start,
+                byte_end,
+            },
+        }
+    }
+
+    fn texts<'a>(segments: &[(&'a str, Option<&facet::MainFeaturesItem>)]) -> Vec<(&'a str, bool)> {
+        segments.iter().map(|(s, f)| (*s, f.is_some())).collect()
+    }
+
+    #[test]
+    fn multibyte() {
+        // "桜" is 3 bytes and "🌸" is 4 bytes
+        let text = "桜 #花見 🌸 #春";
+        let facets = [facet(17, 21, "春"), facet(4, 11, "花見")];
+        assert_eq!(
+            vec![
+                ("桜 ", false),
+                ("#花見", true),
+                (" 🌸 ", false),
+                ("#春", true),
+            ],
+            texts(&segments(text, &facets))
+        );
+    }
+
+    #[test]
+    fn invalid_facets() {
+        let text = "桜 #花見";
+        let facets = [
+            // Splits "桜"
+            facet(1, 3, "x"),
+            // Out of bounds
+            facet(4, 100, "x"),
+            facet(4, 11, "花見"),
+            // Overlaps
+            facet(5, 11, "x"),
+        ];
+        assert_eq!(
+            vec![("桜 ", false), ("#花見", true)],
+            texts(&segments(text, &facets))
+        );
+    }
+
+    #[test]
+    fn shortened_link() {
+        let text = "see example.com/a/b...";
+        let facets = vec![facet::Main {
+            features: vec![Union::Refs(facet::MainFeaturesItem::Link(Box::new(
+                facet::Link {
+                    uri: String::from("https://example.com/a/b/c/d"),
+                },
+            )))],
+            index: facet::ByteSlice {
+                byte_start: 4,
+                byte_end: 22,
+            },
+        }];
+        let spans: Vec<_> = segments(text, &facets)
+            .into_iter()
+            .flat_map(|(s, f)| spans(s, f))
+            .collect();
+        assert_eq!(
+            vec![
+                Span::from("see "),
+                "example.com/a/b...".blue().underlined(),
+                " (https://example.com/a/b/c/d)".dim(),
+            ],
+            spans
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use crate::atp::{Response, ToggleResult};
+
+/// A like, a repost or a follow by the current user, updated before the request is done.
+#[derive(Debug)]
+pub struct Reaction {
+    pub count: u64,
+    pub active: bool,
+    /// The URI of the record of the current user, to undo it.
+    uri: Option<String>,
+    response: Response<ToggleResult>,
+}
+
+impl Reaction {
+    pub fn new(count: u64, uri: Option<String>) -> Self {
+        Self {
+            count,
+            active: uri.is_some(),
+            uri,
+            response: Response::empty(),
+        }
+    }
+
+    pub fn toggle(&mut self, request: impl FnOnce(Option<String>) -> Response<ToggleResult>) {
+        // The record to undo is unknown until the request is done
+        if self.response.is_loading() {
+            return;
+        }
+        self.response = request(self.uri.clone());
+        self.set_active(!self.active);
+    }
+
+    fn set_active(&mut self, active: bool) {
+        if active == self.active {
+            return;
+        }
+        self.active = active;
+        if active {
+            self.count += 1;
+        } else {
+            self.count = self.count.saturating_sub(1);
+        }
+    }
+
+    /// Takes the result of the request, rolling back the update if it has failed.
+    pub fn take_error(&mut self) -> Option<anyhow::Error> {
+        match self.response.take_data()? {
+            Ok(uri) => {
+                self.uri = uri;
+                None
+            }
+            Err(err) => {
+                self.set_active(!self.active);
+                Some(err)
+            }
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use atrium_api::app::bsky::feed::defs::PostView;
 use crossterm::event::KeyCode;
 use ratatui::{
     buffer::Buffer,
@@ -6,19 +9,43 @@ use ratatui::{
 };
 
 use crate::{
+    atp::{ActorList, Response},
     prelude::*,
+    tui::Waker,
     widgets::{
-        molecules::Tab,
+        molecules::{Tab, Toast},
         organisms::TabBar,
-        pages::{Home, Login},
+        pages::{
+            Accounts, Actors, Compose, Home, Login, Notifications, Profile, Search, Thread, Unlock,
+        },
     },
 };
 
+/// How often the unread count of the notifications is updated.
+const UNREAD_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Default)]
 pub struct View {
     id: ViewID,
+    /// Handle of the active account
+    handle: Option<String>,
+    /// Message of the last error reported by the page
+    error: Option<String>,
+    /// The number of unread notifications
+    unread: u64,
+    unread_res: Response<crate::atp::UnreadCountResult>,
+    /// Wakes the event loop when the unread count is due to be requested again
+    unread_timer: Response<()>,
     home: Home,
     login: Login,
+    accounts: Accounts,
+    unlock: Unlock,
+    compose: Compose,
+    thread: Thread,
+    profile: Profile,
+    actors: Actors,
+    notifications: Notifications,
+    search: Search,
 }
 
 macro_rules! inner {
@@ -26,17 +53,59 @@ macro_rules! inner {
         match $self.id {
             ViewID::Home => &$self.home,
             ViewID::Login { .. } => &$self.login,
+            ViewID::Accounts => &$self.accounts,
+            ViewID::Unlock => &$self.unlock,
+            ViewID::Compose { .. } => &$self.compose,
+            ViewID::Thread { .. } => &$self.thread,
+            ViewID::Profile { .. } => &$self.profile,
+            ViewID::Actors { .. } => &$self.actors,
+            ViewID::Notifications => &$self.notifications,
+            ViewID::Search => &$self.search,
         }
     };
     (mut $self:ident) => {
         match $self.id {
             ViewID::Home => &mut $self.home,
             ViewID::Login { .. } => &mut $self.login,
+            ViewID::Accounts => &mut $self.accounts,
+            ViewID::Unlock => &mut $self.unlock,
+            ViewID::Compose { .. } => &mut $self.compose,
+            ViewID::Thread { .. } => &mut $self.thread,
+            ViewID::Profile { .. } => &mut $self.profile,
+            ViewID::Actors { .. } => &mut $self.actors,
+            ViewID::Notifications => &mut $self.notifications,
+            ViewID::Search => &mut $self.search,
         }
     };
 }
 
 impl View {
+    /// Updates the unread count of the notifications from time to time while signed in.
+    fn check_unread(&mut self, app: &mut App) {
+        if let Some(result) = self.unread_res.take_data() {
+            match result {
+                Ok(count) => self.unread = count,
+                // Not worth interrupting the user
+                Err(err) => tracing::warn!("failed to get the unread count: {err:#}"),
+            }
+        }
+        if self.id == ViewID::Notifications {
+            self.unread = 0;
+            self.unread_res.cancel();
+            self.unread_timer = unread_timer(app.waker());
+            return;
+        }
+        let signed_in = !app.is_locked()
+            && app.atp().account().did.is_some()
+            && !matches!(self.id, ViewID::Login { .. } | ViewID::Unlock);
+        // The timer is empty once it has gone off, or before the first check
+        self.unread_timer.take_data();
+        if signed_in && self.unread_timer.is_empty() && self.unread_res.is_empty() {
+            self.unread_res = app.atp().get_unread_count();
+            self.unread_timer = unread_timer(app.waker());
+        }
+    }
+
     fn widget_ref(&self) -> &dyn WidgetRef {
         inner!(self)
     }
@@ -48,6 +117,11 @@ impl View {
     }
 }
 
+/// Goes off after [`UNREAD_CHECK_INTERVAL`], rendering the view even if nothing else happens.
+fn unread_timer(waker: &Waker) -> Response<()> {
+    Response::new(waker, tokio::time::sleep(UNREAD_CHECK_INTERVAL))
+}
+
 impl WidgetRef for View {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         let [tabbar_area, main_area] =
@@ -57,28 +131,73 @@ impl WidgetRef for View {
 
         TabBar::from_iter([
             Tab::new("1. Login").selected(matches!(self.id, ViewID::Login { .. })),
-            Tab::new("2. Home").selected(matches!(self.id, ViewID::Home)),
-            Tab::new("3. Settings").active(false),
+            Tab::new("2. Home").selected(matches!(
+                self.id,
+                ViewID::Home
+                    | ViewID::Compose { .. }
+                    | ViewID::Thread { .. }
+                    | ViewID::Profile { .. }
+                    | ViewID::Actors { .. }
+            )),
+            Tab::new(match &self.handle {
+                Some(handle) => format!("3. @{handle}"),
+                None => String::from("3. Accounts"),
+            })
+            .selected(matches!(self.id, ViewID::Accounts)),
+            Tab::new("4. Notifications")
+                .badge(self.unread)
+                .selected(matches!(self.id, ViewID::Notifications)),
+            Tab::new("5. Search").selected(matches!(self.id, ViewID::Search)),
+            Tab::new("6. Settings").active(false),
         ])
         .render_ref(tabbar_area, buf);
 
-        self.widget_ref().render_ref(main_area, buf)
+        self.widget_ref().render_ref(main_area, buf);
+
+        if let Some(error) = &self.error {
+            Toast::new(error.as_str()).render_ref(main_area, buf);
+        }
     }
 }
 
 impl crate::app::EventHandler for View {
     fn on_render(&mut self, app: &mut App) {
+        if app.view_id() == &ViewID::Notifications && self.id != ViewID::Notifications {
+            // Shows the latest notifications every time
+            self.notifications = Notifications::default();
+        }
         self.id = app.view_id().clone();
-        self.event_handler_mut().on_render(app)
+        self.check_unread(app);
+        self.handle = app.atp().account().handle.map(|h| h.as_str().to_string());
+        self.event_handler_mut().on_render(app);
+        self.error = app.error().map(ToString::to_string);
     }
 
     fn on_key(&mut self, ev: crossterm::event::KeyEvent, app: &mut App) {
-        if ev.code == KeyCode::Char('1') {
+        if self.error.is_some() {
+            if ev.code == KeyCode::Char('r') {
+                app.clear_error();
+                self.event_handler_mut().on_retry(app);
+                return;
+            } else if ev.code == KeyCode::Char('x') {
+                app.clear_error();
+                return;
+            }
+        }
+        if app.is_locked() {
+            // No page can be used until the sessions are decrypted
+        } else if ev.code == KeyCode::Char('1') {
             app.set_view_id(ViewID::Login {
                 resume_session: false,
             });
         } else if ev.code == KeyCode::Char('2') {
             app.set_view_id(ViewID::Home);
+        } else if ev.code == KeyCode::Char('3') {
+            app.set_view_id(ViewID::Accounts);
+        } else if ev.code == KeyCode::Char('4') && app.atp().account().did.is_some() {
+            app.set_view_id(ViewID::Notifications);
+        } else if ev.code == KeyCode::Char('5') && app.atp().account().did.is_some() {
+            app.set_view_id(ViewID::Search);
         }
         self.event_handler_mut().on_key(ev, app)
     }
@@ -92,12 +211,42 @@ impl crate::app::EventHandler for View {
     fn focus_in_textarea(&self) -> bool {
         self.event_handler().focus_in_textarea()
     }
+    fn is_animating(&self) -> bool {
+        self.event_handler().is_animating()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ViewID {
-    Login { resume_session: bool },
+    Login {
+        resume_session: bool,
+    },
     Home,
+    Accounts,
+    /// Asks for the passphrase of the encrypted sessions.
+    Unlock,
+    /// Writes a new post, replying to `reply_to` and quoting `quote` if any.
+    Compose {
+        reply_to: Option<Box<PostView>>,
+        quote: Option<Box<PostView>>,
+    },
+    /// Reads the conversation around the post at `uri`.
+    Thread {
+        uri: String,
+    },
+    /// Shows the profile and the posts of `actor`, a handle or a DID.
+    Profile {
+        actor: String,
+    },
+    /// Lists what others have done to the account.
+    Notifications,
+    /// Finds posts and accounts.
+    Search,
+    /// Lists the followers or the follows of `actor`.
+    Actors {
+        actor: String,
+        list: ActorList,
+    },
 }
 
 impl Default for ViewID {